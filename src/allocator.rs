@@ -0,0 +1,148 @@
+use allocator_api2::alloc::{AllocError, Allocator, Layout};
+use core::ptr::NonNull;
+
+use super::Arena;
+
+/// Lets standard collections, such as `allocator_api2::vec::Vec` or
+/// `hashbrown::HashMap`, allocate their storage in an arena. Memory is only
+/// given back when the arena is reset, except for the most recent
+/// allocation, which can be grown, shrunk and freed in place.
+unsafe impl Allocator for &Arena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = self.alloc_layout(layout).ok_or(AllocError)?;
+        let ptr = NonNull::new(ptr).ok_or(AllocError)?;
+
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.resize_in_place(ptr.as_ptr(), layout.size(), 0);
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let aligned = ptr.as_ptr() as usize & (new_layout.align() - 1) == 0;
+
+        if aligned && self.resize_in_place(ptr.as_ptr(), old_layout.size(), new_layout.size()) {
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+
+        let new_ptr = self.allocate(new_layout)?;
+        let size = old_layout.size();
+        core::ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr() as *mut u8, size);
+
+        Ok(new_ptr)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if ptr.as_ptr() as usize & (new_layout.align() - 1) == 0 {
+            // Shrinking in place always fits, releasing the tail is a bonus.
+            self.resize_in_place(ptr.as_ptr(), old_layout.size(), new_layout.size());
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+
+        let new_ptr = self.allocate(new_layout)?;
+        let size = new_layout.size();
+        core::ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr() as *mut u8, size);
+
+        Ok(new_ptr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Arena;
+    use allocator_api2::boxed::Box;
+    use allocator_api2::vec::Vec;
+    use hashbrown::HashMap;
+
+    #[test]
+    fn test_allocator_vec() {
+        let arena = Arena::new(1024);
+        let mut vec = Vec::new_in(&arena);
+
+        for i in 0..64u32 {
+            vec.push(i);
+        }
+
+        assert_eq!(vec.len(), 64);
+        assert!(vec.iter().copied().eq(0..64));
+
+        // The vector was the only allocation, so it grew in place.
+        assert_eq!(arena.occupied(), vec.capacity() * 4);
+    }
+
+    #[test]
+    fn test_allocator_vec_relocates() {
+        let arena = Arena::new(1024);
+        let mut first = Vec::with_capacity_in(4, &arena);
+        let mut second = Vec::with_capacity_in(4, &arena);
+
+        first.extend_from_slice(&[1u32, 2, 3, 4]);
+        second.extend_from_slice(&[5u32, 6, 7, 8]);
+        first.push(5);
+
+        assert_eq!(first.as_slice(), &[1, 2, 3, 4, 5]);
+        assert_eq!(second.as_slice(), &[5, 6, 7, 8]);
+        assert_eq!(arena.occupied(), 32 + first.capacity() * 4);
+    }
+
+    #[test]
+    fn test_allocator_deallocate_last() {
+        let arena = Arena::new(1024);
+        let boxed = Box::new_in(42u64, &arena);
+
+        assert_eq!(*boxed, 42);
+        assert_eq!(arena.occupied(), 8);
+
+        drop(boxed);
+
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_allocator_shrink() {
+        let arena = Arena::new(1024);
+        let mut vec = Vec::with_capacity_in(64, &arena);
+
+        vec.extend_from_slice(&[1u8, 2, 3]);
+        vec.shrink_to_fit();
+
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        assert_eq!(arena.occupied(), 3);
+    }
+
+    #[test]
+    fn test_allocator_hash_map() {
+        let arena = Arena::new(16 * 1024);
+        let mut map = HashMap::new_in(&arena);
+
+        for i in 0..100u32 {
+            map.insert(i, i * 2);
+        }
+
+        for i in 0..100u32 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+
+        assert!(arena.occupied() > 0);
+    }
+
+    #[test]
+    fn test_allocator_exhausted() {
+        let arena = Arena::new(16);
+        let mut vec: Vec<u32, _> = Vec::new_in(&arena);
+
+        assert!(vec.try_reserve(4).is_ok());
+        assert!(vec.try_reserve(64).is_err());
+    }
+}
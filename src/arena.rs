@@ -1,39 +1,277 @@
+use std::alloc::Layout;
 use std::boxed::Box;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
 
-use super::{ArenaBox, ArenaArray, ArenaString, ArenaTable, ArenaList};
+use super::global::unscoped;
+use super::{ArenaBox, ArenaArray, ArenaString, ArenaTable, ArenaList, ArenaMap, ArenaSet, ArenaVec, ArenaDeque};
 
-/// An arena is a fixed size memory buffer that can be used to allocate
-/// memory for objects that have a lifetime that is bound to the arena.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
+/// Decides what a growable arena does with the chunks it chained on
+/// when it is reset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+pub enum ResetPolicy {
+    /// Keep every chunk so later allocations can reuse them.
+    #[default]
+    Retain,
+    /// Free every chunk except the first one.
+    Release,
+}
+
+/// A position in an arena that it can later be rewound to with
+/// `Arena::rewind`. Everything allocated after the checkpoint was taken
+/// is released by the rewind and its generation is marked as stale. The
+/// checkpoint is closed by the rewind, as are the ones taken after it.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Checkpoint {
+    chunk: usize,
+    offset: usize,
+    used: usize,
+    generation: usize,
+    drops: usize,
+}
+
+/// A guard that rewinds its arena to the checkpoint taken when the scope
+/// was opened once it is dropped. The scope derefs to the arena, so
+/// allocations can be made through it directly. It borrows the arena
+/// mutably, so everything allocated through it is gone before the rewind.
+#[derive(Debug)]
+pub struct ArenaScope<'a> {
+    arena: &'a mut Arena,
+    checkpoint: Checkpoint,
+}
+
+/// The allocation interface shared by `Arena` and `SyncArena`, which lets
+/// boxes and arrays be allocated in either of them.
+///
+/// # Safety
+///
+/// `alloc` must return memory that stays valid and is not handed out again
+/// for as long as `is_live` returns true for the generation it was
+/// allocated at.
+pub unsafe trait RawArena {
+    fn alloc<T>(&self, len: usize) -> Option<*mut T>;
+
+    fn generation(&self) -> usize;
+
+    fn is_live(&self, generation: usize) -> bool;
+
+    /// # Safety
+    ///
+    /// See `Arena::register_drop`.
+    unsafe fn register_drop<T>(&self, ptr: *mut T);
+}
+
+/// A destructor registered with an arena for a value living in it.
+#[derive(Debug)]
+pub(crate) struct DropEntry {
+    ptr: *mut u8,
+    drop: unsafe fn(*mut u8),
+}
+
+impl DropEntry {
+    pub(crate) fn new<T>(ptr: *mut T) -> DropEntry {
+        DropEntry {
+            ptr: ptr as *mut u8,
+            drop: drop_value::<T>,
+        }
+    }
+
+    pub(crate) unsafe fn run(self) {
+        (self.drop)(self.ptr);
+    }
+}
+
+unsafe fn drop_value<T>(ptr: *mut u8) {
+    core::ptr::drop_in_place(ptr as *mut T);
+}
+
+/// An arena is a memory buffer that can be used to allocate memory for
+/// objects that have a lifetime that is bound to the arena.
+/// A fixed arena owns a single chunk, while a growable arena chains on
+/// additional chunks, up to a maximum size, when the current one is
+/// exhausted. Chunks are never moved, so existing allocations stay valid.
+/// Values that need to be dropped are dropped in reverse order of
+/// allocation when the arena is reset, rewound past them or dropped.
+#[derive(Debug)]
 pub struct Arena {
-    data: Box<[u8]>,
+    chunks: RefCell<Vec<Box<[u8]>>>,
+    chunk: Cell<usize>,
     offset: Cell<usize>,
+    used: Cell<usize>,
     generation: Cell<usize>,
+    floor: Cell<usize>,
+    checkpoints: RefCell<Vec<Checkpoint>>,
+    drops: RefCell<Vec<DropEntry>>,
+    max_size: usize,
+    policy: ResetPolicy,
 }
 
 impl Arena {
     pub fn new(size: usize) -> Arena {
+        Self::growable(size, size)
+    }
+
+    pub fn growable(initial: usize, max: usize) -> Arena {
         Arena {
-            data: vec![0; size].into_boxed_slice(),
+            chunks: RefCell::new(vec![vec![0; initial].into_boxed_slice()]),
+            chunk: Cell::new(0),
             offset: Cell::new(0),
+            used: Cell::new(0),
             generation: Cell::new(0),
+            floor: Cell::new(0),
+            checkpoints: RefCell::new(Vec::new()),
+            drops: RefCell::new(Vec::new()),
+            max_size: max.max(initial),
+            policy: ResetPolicy::default(),
         }
     }
 
+    pub fn with_reset_policy(mut self, policy: ResetPolicy) -> Arena {
+        self.policy = policy;
+        self
+    }
+
     pub fn alloc<T>(&self, len: usize) -> Option<*mut T> {
-        let size = core::mem::size_of::<T>();
-        let align = core::mem::align_of::<T>();
-        let offset = (self.offset.get() + align - 1) & !(align - 1);
-        let new_offset = offset + (size * len);
+        let layout = Layout::array::<T>(len).ok()?;
 
-        if new_offset <= self.size() {
-            let ptr = &self.data[offset] as *const u8 as *mut T;
-            self.offset.set(new_offset);
+        self.alloc_layout(layout).map(|ptr| ptr as *mut T)
+    }
 
-            Some(ptr)
-        } else {
-            None
+    /// Allocates a block of memory that fits `layout`. Zero-sized requests
+    /// do not take up any space and get a dangling, well-aligned pointer.
+    /// Returns `None` when the arena cannot make room for the block.
+    pub fn alloc_layout(&self, layout: Layout) -> Option<*mut u8> {
+        if layout.size() == 0 {
+            return Some(layout.align() as *mut u8);
+        }
+
+        self.bump(layout.size(), layout.align())
+    }
+
+    fn bump(&self, size: usize, align: usize) -> Option<*mut u8> {
+        let mut chunks = self.chunks.borrow_mut();
+
+        // Walk the chunks on copies of the position, so that a failed
+        // allocation does not strand the room left in the chunks it skipped.
+        let mut index = self.chunk.get();
+        let mut offset = self.offset.get();
+        let mut used = self.used.get();
+
+        loop {
+            let chunk = &mut chunks[index];
+            let chunk_len = chunk.len();
+
+            // Chunks are only byte aligned, so align the actual address.
+            let base = chunk.as_mut_ptr() as usize;
+            let start = (base + offset).checked_add(align - 1)? & !(align - 1);
+            let start = start - base;
+
+            if start <= chunk_len && size <= chunk_len - start {
+                self.chunk.set(index);
+                self.offset.set(start + size);
+                self.used.set(used);
+
+                return Some(unsafe { chunk.as_mut_ptr().add(start) });
+            }
+
+            // The current chunk is exhausted, move on to the next retained
+            // chunk or chain on a new one if we are still allowed to grow.
+            let next = index + 1;
+
+            if next == chunks.len() {
+                let total: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+                let remaining = self.max_size - total;
+
+                if size > remaining {
+                    return None;
+                }
+
+                // Leave room for aligning the start of the new chunk.
+                let needed = size.saturating_add(align - 1);
+                let len = chunk_len.saturating_mul(2).max(needed).min(remaining);
+                let mut chunk = Vec::new();
+
+                // Running out of memory is reported like any other failed
+                // allocation instead of aborting. Chunks outlive any scope the
+                // arena is used in, so they come from the system allocator.
+                unscoped(|| chunk.try_reserve_exact(len)).ok()?;
+                chunk.resize(len, 0);
+
+                // The budget may leave less room than aligning the start
+                // needs. Such a chunk could not hold the block, so it is not
+                // kept around to use up the rest of the budget.
+                let base = chunk.as_ptr() as usize;
+                let start = base.checked_add(align - 1)? & !(align - 1);
+
+                if start - base > len - size {
+                    return None;
+                }
+
+                unscoped(|| chunks.push(chunk.into_boxed_slice()));
+            }
+
+            used += chunk_len;
+            index = next;
+            offset = 0;
+        }
+    }
+
+    /// Resizes the allocation at `ptr` in place, which is only possible when
+    /// it is the most recent allocation and the current chunk has room for
+    /// `new_size` bytes. Shrinking releases the tail for later allocations.
+    pub(crate) fn resize_in_place(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> bool {
+        let chunks = self.chunks.borrow();
+        let chunk = &chunks[self.chunk.get()];
+        let base = chunk.as_ptr() as usize;
+        let start = ptr as usize;
+
+        if start < base || start.checked_add(old_size) != Some(base + self.offset.get()) {
+            return false;
+        }
+
+        match (start - base).checked_add(new_size) {
+            Some(new_offset) if new_offset <= chunk.len() => {
+                self.offset.set(new_offset);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether `ptr` points into one of the arena's chunks.
+    pub(crate) fn contains(&self, ptr: *const u8) -> bool {
+        self.chunks
+            .borrow()
+            .iter()
+            .any(|chunk| chunk.as_ptr_range().contains(&ptr))
+    }
+
+    /// Registers `ptr` to be dropped in place when the arena is reset,
+    /// rewound past it or dropped. Does nothing for types without drop glue.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to an initialized `T` allocated from this arena that
+    /// is not dropped by anyone else.
+    pub unsafe fn register_drop<T>(&self, ptr: *mut T) {
+        if core::mem::needs_drop::<T>() {
+            unscoped(|| self.drops.borrow_mut().push(DropEntry::new(ptr)));
+        }
+    }
+
+    fn run_drops(&self, len: usize) {
+        // Take the entries out first, destructors are free to use the arena.
+        let entries = {
+            let mut drops = self.drops.borrow_mut();
+            let len = len.min(drops.len());
+            drops.split_off(len)
+        };
+
+        for entry in entries.into_iter().rev() {
+            unsafe { entry.run() };
         }
     }
 
@@ -41,58 +279,637 @@ impl Arena {
         self.generation.get()
     }
 
-    pub fn make_box<T>(&self) -> Option<ArenaBox<T>> {
+    /// Returns whether memory handed out while the arena was at `generation`
+    /// is still owned by its allocation, i.e. it has not been released by
+    /// a reset or a rewind since.
+    ///
+    /// A rewind hands the generations it released out again to later
+    /// checkpoints. No handle from before the rewind can be alive by then,
+    /// since it needs exclusive access.
+    pub fn is_live(&self, generation: usize) -> bool {
+        generation >= self.floor.get() && generation <= self.generation.get()
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        let generation = self.generation.get();
+
+        let checkpoint = Checkpoint {
+            chunk: self.chunk.get(),
+            offset: self.offset.get(),
+            used: self.used.get(),
+            generation,
+            drops: self.drops.borrow().len(),
+        };
+
+        // Allocations made after the checkpoint get their own generation so
+        // a rewind can invalidate them without touching older ones. The
+        // generation is one past the floor for every checkpoint still open.
+        unscoped(|| self.checkpoints.borrow_mut().push(checkpoint));
+        self.generation.set(generation + 1);
+
+        checkpoint
+    }
+
+    /// Releases everything allocated after `checkpoint` was taken. Like a
+    /// reset this requires exclusive access, so no handle into the released
+    /// memory can be alive when it happens.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        unsafe { self.rewind_unchecked(checkpoint) }
+    }
+
+    /// Rewinds through a shared reference, which lets the tests check that
+    /// handles into the released memory notice they are stale.
+    ///
+    /// # Safety
+    ///
+    /// Handles into memory allocated after `checkpoint` must not be
+    /// dereferenced afterwards.
+    pub(crate) unsafe fn rewind_unchecked(&self, checkpoint: Checkpoint) {
+        let Some(index) = checkpoint.generation.checked_sub(self.floor.get()) else {
+            return;
+        };
+
+        // The checkpoint was already closed by a reset or a rewind.
+        if self.checkpoints.borrow().get(index) != Some(&checkpoint) {
+            return;
+        }
+
+        self.run_drops(checkpoint.drops);
+        self.checkpoints.borrow_mut().truncate(index);
+
+        self.generation.set(checkpoint.generation);
+        self.chunk.set(checkpoint.chunk);
+        self.offset.set(checkpoint.offset);
+        self.used.set(checkpoint.used);
+    }
+
+    pub fn scope(&mut self) -> ArenaScope<'_> {
+        let checkpoint = self.checkpoint();
+
+        ArenaScope {
+            arena: self,
+            checkpoint,
+        }
+    }
+
+    pub fn make_box<T>(&self) -> Option<ArenaBox<'_, MaybeUninit<T>>> {
         ArenaBox::new(self)
     }
 
-    pub fn make_array<T>(&self, capacity: usize) -> Option<ArenaArray<T>> {
-        ArenaArray::new(self, 0, capacity)
+    pub fn make_array<T>(&self, capacity: usize) -> Option<ArenaArray<'_, T>> {
+        ArenaArray::with_capacity(self, capacity)
+    }
+
+    pub fn make_vec<T>(&self, capacity: usize) -> Option<ArenaVec<'_, T>> {
+        ArenaVec::with_capacity(self, capacity)
     }
 
-    pub fn make_string(&self, capacity: usize) -> Option<ArenaString> {
+    pub fn make_deque<T>(&self, capacity: usize) -> Option<ArenaDeque<'_, T>> {
+        ArenaDeque::with_capacity(self, capacity)
+    }
+
+    pub fn make_string(&self, capacity: usize) -> Option<ArenaString<'_>> {
         ArenaString::new(self, capacity)
     }
 
-    pub fn make_table<T>(&self, capacity: usize) -> Option<ArenaTable<T>> {
+    pub fn make_table<T>(&self, capacity: usize) -> Option<ArenaTable<'_, T>> {
         ArenaTable::new(self, capacity)
     }
 
-    pub fn make_list<T>(&self) -> Option<ArenaList<T>> {
+    pub fn make_map<K: Hash + Eq, V>(&self, capacity: usize) -> Option<ArenaMap<'_, K, V>> {
+        ArenaMap::new(self, capacity)
+    }
+
+    pub fn make_set<T: Hash + Eq>(&self, capacity: usize) -> Option<ArenaSet<'_, T>> {
+        ArenaSet::new(self, capacity)
+    }
+
+    pub fn make_list<T>(&self) -> Option<ArenaList<'_, T>> {
         Some(ArenaList::new(self))
     }
 
-    pub fn push<T>(&self, value: T) -> Option<ArenaBox<T>> {
+    pub fn push<T>(&self, value: T) -> Option<ArenaBox<'_, T>> {
         ArenaBox::from_value(self, value)
     }
 
-    pub fn push_array<T>(&self, values: &[T]) -> Option<ArenaArray<T>> {
+    pub fn push_array<T: Clone>(&self, values: &[T]) -> Option<ArenaArray<'_, T>> {
         ArenaArray::from_slice(self, values)
     }
 
-    pub fn push_string(&self, str: impl AsRef<str>) -> Option<ArenaString> {
+    pub fn push_string(&self, str: impl AsRef<str>) -> Option<ArenaString<'_>> {
         ArenaString::from_str(self, str.as_ref())
     }
 
-    pub fn reset(&self) {
-        let offset = self.offset.get();
+    /// Releases every allocation at once. This requires exclusive access, so
+    /// no handle into the arena can be alive when it happens.
+    pub fn reset(&mut self) {
+        self.run_drops(0);
+
+        let offset = self.occupied();
 
         // If we have allocated any memory, increment the generation
         if offset > 0 {
             self.generation.set(self.generation.get() + 1);
         }
 
+        self.floor.set(self.generation.get());
+        self.checkpoints.get_mut().clear();
+
+        if self.policy == ResetPolicy::Release {
+            self.chunks.get_mut().truncate(1);
+        }
+
+        self.chunk.set(0);
         self.offset.set(0);
+        self.used.set(0);
+    }
+
+    pub fn reset_policy(&self) -> ResetPolicy {
+        self.policy
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
     }
 
     pub fn size(&self) -> usize {
-        self.data.len()
+        self.chunks.borrow().iter().map(|chunk| chunk.len()).sum()
     }
 
     pub fn occupied(&self) -> usize {
-        self.offset.get()
+        self.used.get() + self.offset.get()
     }
 
     pub fn is_full(&self) -> bool {
         self.occupied() == self.size()
     }
 }
+
+unsafe impl RawArena for Arena {
+    fn alloc<T>(&self, len: usize) -> Option<*mut T> {
+        Arena::alloc(self, len)
+    }
+
+    fn generation(&self) -> usize {
+        Arena::generation(self)
+    }
+
+    fn is_live(&self, generation: usize) -> bool {
+        Arena::is_live(self, generation)
+    }
+
+    unsafe fn register_drop<T>(&self, ptr: *mut T) {
+        Arena::register_drop(self, ptr)
+    }
+}
+
+// Compares the memory and position of arenas like a derive would. The drop
+// list holds function pointers, which have no meaningful order, so it is
+// left out.
+impl PartialEq for Arena {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Arena {}
+
+impl PartialOrd for Arena {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Arena {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.chunks
+            .cmp(&other.chunks)
+            .then_with(|| self.chunk.cmp(&other.chunk))
+            .then_with(|| self.offset.cmp(&other.offset))
+            .then_with(|| self.used.cmp(&other.used))
+            .then_with(|| self.generation.cmp(&other.generation))
+            .then_with(|| self.floor.cmp(&other.floor))
+            .then_with(|| self.checkpoints.cmp(&other.checkpoints))
+            .then_with(|| self.max_size.cmp(&other.max_size))
+            .then_with(|| self.policy.cmp(&other.policy))
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        self.run_drops(0);
+    }
+}
+
+impl ArenaScope<'_> {
+    pub fn checkpoint(&self) -> Checkpoint {
+        self.checkpoint
+    }
+}
+
+impl Deref for ArenaScope<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        self.arena
+    }
+}
+
+impl DerefMut for ArenaScope<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.arena
+    }
+}
+
+impl Drop for ArenaScope<'_> {
+    fn drop(&mut self) {
+        self.arena.rewind(self.checkpoint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arena, ResetPolicy};
+    use std::alloc::Layout;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Tracked(u32, Rc<RefCell<Vec<u32>>>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    #[test]
+    fn test_growable_arena() {
+        let arena = Arena::growable(16, 1024);
+
+        assert_eq!(arena.size(), 16);
+        assert_eq!(arena.max_size(), 1024);
+
+        let boxes: Vec<_> = (0..8).map(|i| arena.push(i as u32).unwrap()).collect();
+
+        assert_eq!(arena.chunk_count(), 2);
+        assert_eq!(arena.size(), 48);
+        assert_eq!(arena.occupied(), 32);
+        assert!(!arena.is_full());
+
+        for (i, boxed) in boxes.iter().enumerate() {
+            assert_eq!(**boxed, i as u32);
+        }
+    }
+
+    #[test]
+    fn test_growable_arena_failed_alloc() {
+        let mut arena = Arena::growable(16, 1024);
+
+        for i in 0..16 {
+            arena.push(i as u64).unwrap();
+        }
+
+        arena.reset();
+        arena.push(1u64).unwrap();
+
+        // Too large for any chunk, but the room left in the retained chunks
+        // is not given up for it.
+        assert!(arena.make_array::<u8>(2048).is_none());
+        assert_eq!(arena.occupied(), 8);
+
+        arena.push(2u64).unwrap();
+
+        assert_eq!(arena.occupied(), 16);
+    }
+
+    #[test]
+    fn test_arena_eq() {
+        let first = Arena::new(16);
+        let second = Arena::new(16);
+
+        assert_eq!(first, second);
+
+        second.push(1u8).unwrap();
+
+        assert!(first < second);
+    }
+
+    #[test]
+    fn test_growable_arena_max_size() {
+        let arena = Arena::growable(16, 32);
+
+        assert!(arena.make_array::<u8>(12).is_some());
+        assert!(arena.make_array::<u8>(8).is_some());
+        assert!(arena.make_array::<u8>(16).is_none());
+        assert!(arena.make_array::<u8>(8).is_some());
+
+        assert_eq!(arena.size(), 32);
+        assert!(arena.is_full());
+    }
+
+    #[test]
+    fn test_growable_arena_large_allocation() {
+        let arena = Arena::growable(16, 1024);
+        let array = arena.make_array::<u8>(100).unwrap();
+
+        assert_eq!(array.capacity(), 100);
+        assert_eq!(arena.chunk_count(), 2);
+        assert_eq!(arena.size(), 116);
+    }
+
+    #[test]
+    fn test_growable_arena_retain() {
+        let mut arena = Arena::growable(16, 1024);
+
+        for i in 0..8 {
+            arena.push(i as u32).unwrap();
+        }
+
+        arena.reset();
+
+        assert_eq!(arena.reset_policy(), ResetPolicy::Retain);
+        assert_eq!(arena.chunk_count(), 2);
+        assert_eq!(arena.occupied(), 0);
+
+        for i in 0..8 {
+            arena.push(i as u32).unwrap();
+        }
+
+        assert_eq!(arena.chunk_count(), 2);
+    }
+
+    #[test]
+    fn test_growable_arena_release() {
+        let mut arena = Arena::growable(16, 1024).with_reset_policy(ResetPolicy::Release);
+
+        for i in 0..8 {
+            arena.push(i as u32).unwrap();
+        }
+
+        arena.reset();
+
+        assert_eq!(arena.chunk_count(), 1);
+        assert_eq!(arena.size(), 16);
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_alloc_overflow() {
+        let arena = Arena::growable(16, usize::MAX);
+
+        assert!(arena.alloc::<u64>(usize::MAX).is_none());
+        assert!(arena.alloc::<u64>(usize::MAX / 8 + 1).is_none());
+        assert!(arena.alloc_layout(Layout::from_size_align(usize::MAX / 2, 1).unwrap()).is_none());
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_alloc_zero_sized() {
+        let arena = Arena::new(4);
+
+        arena.push(1u32).unwrap();
+
+        let unit = arena.push(()).unwrap();
+        let array = arena.make_array::<()>(1000).unwrap();
+        let ptr = arena.alloc::<[u64; 0]>(1).unwrap();
+
+        assert_eq!(*unit, ());
+        assert_eq!(array.capacity(), 1000);
+        assert_eq!(ptr as usize % 8, 0);
+        assert_eq!(arena.occupied(), 4);
+        assert!(arena.is_full());
+    }
+
+    #[test]
+    fn test_alloc_over_aligned() {
+        #[repr(align(64))]
+        struct Aligned(u8);
+
+        let arena = Arena::growable(128, 1024);
+
+        arena.push(1u8).unwrap();
+
+        let first = arena.push(Aligned(2)).unwrap();
+        let second = arena.push(Aligned(3)).unwrap();
+
+        assert_eq!(first.as_ptr() as usize % 64, 0);
+        assert_eq!(second.as_ptr() as usize % 64, 0);
+        assert_eq!(first.0, 2);
+        assert_eq!(second.0, 3);
+    }
+
+    #[test]
+    fn test_alloc_over_aligned_at_max_size() {
+        let arena = Arena::growable(16, 116);
+
+        // The last 100 bytes of the budget cannot fit the block once its
+        // start is aligned, so no chunk is chained on for it.
+        let layout = Layout::from_size_align(64, 1 << 20).unwrap();
+
+        assert!(arena.alloc_layout(layout).is_none());
+        assert_eq!(arena.chunk_count(), 1);
+        assert_eq!(arena.size(), 16);
+
+        assert!(arena.make_array::<u8>(64).is_some());
+        assert_eq!(arena.chunk_count(), 2);
+    }
+
+    #[test]
+    fn test_alloc_layout() {
+        let arena = Arena::new(1024);
+
+        arena.push(1u8).unwrap();
+
+        let layout = Layout::from_size_align(24, 32).unwrap();
+        let ptr = arena.alloc_layout(layout).unwrap();
+
+        assert_eq!(ptr as usize % 32, 0);
+        assert!(arena.alloc_layout(Layout::from_size_align(2048, 1).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_checkpoint_rewind() {
+        let mut arena = Arena::new(1024);
+        let before = arena.push(1u32).unwrap().generation();
+        let checkpoint = arena.checkpoint();
+
+        let after = arena.push(2u32).unwrap();
+        let (ptr, generation) = (after.as_ptr(), after.generation());
+        arena.push(3u32).unwrap();
+
+        assert_eq!(arena.occupied(), 12);
+
+        arena.rewind(checkpoint);
+
+        assert_eq!(arena.occupied(), 4);
+        assert!(arena.is_live(before));
+        assert!(!arena.is_live(generation));
+
+        let again = arena.push(4u32).unwrap();
+
+        assert_eq!(arena.occupied(), 8);
+        assert_eq!(again.as_ptr(), ptr);
+        assert!(arena.is_live(again.generation()));
+    }
+
+    #[test]
+    fn test_checkpoint_rewind_across_chunks() {
+        let mut arena = Arena::growable(16, 1024);
+
+        arena.push(1u64).unwrap();
+        let checkpoint = arena.checkpoint();
+
+        for i in 0..4 {
+            arena.push(i as u64).unwrap();
+        }
+
+        assert_eq!(arena.chunk_count(), 2);
+
+        arena.rewind(checkpoint);
+
+        assert_eq!(arena.occupied(), 8);
+        assert_eq!(arena.chunk_count(), 2);
+    }
+
+    #[test]
+    fn test_nested_checkpoints() {
+        let mut arena = Arena::new(1024);
+        let outer = arena.checkpoint();
+        let first = arena.push(1u32).unwrap().generation();
+        let inner = arena.checkpoint();
+        let second = arena.push(2u32).unwrap().generation();
+
+        arena.rewind(inner);
+
+        assert!(arena.is_live(first));
+        assert!(!arena.is_live(second));
+        assert_eq!(arena.occupied(), 4);
+
+        arena.rewind(outer);
+
+        assert!(!arena.is_live(first));
+        assert_eq!(arena.occupied(), 0);
+
+        // The inner checkpoint was released by the outer rewind.
+        arena.push(3u32).unwrap();
+        arena.rewind(inner);
+
+        assert_eq!(arena.occupied(), 4);
+    }
+
+    #[test]
+    fn test_repeated_rewind() {
+        let mut arena = Arena::new(1024);
+        let generation = arena.push(1u32).unwrap().generation();
+
+        for i in 0..1000 {
+            let checkpoint = arena.checkpoint();
+            arena.push(i as u64).unwrap();
+            arena.rewind(checkpoint);
+        }
+
+        assert!(arena.checkpoints.get_mut().is_empty());
+        assert_eq!(arena.generation(), generation);
+        assert_eq!(arena.occupied(), 4);
+    }
+
+    #[test]
+    fn test_checkpoint_after_reset() {
+        let mut arena = Arena::new(1024);
+
+        arena.push(1u32).unwrap();
+        let checkpoint = arena.checkpoint();
+        arena.push(2u32).unwrap();
+
+        arena.reset();
+        arena.push(3u32).unwrap();
+        arena.rewind(checkpoint);
+
+        assert_eq!(arena.occupied(), 4);
+    }
+
+    #[test]
+    fn test_arena_scope() {
+        let mut arena = Arena::new(1024);
+        let outside = arena.push(1u32).unwrap().generation();
+
+        let generation = {
+            let mut scope = arena.scope();
+            let inside = scope.push(2u32).unwrap().generation();
+
+            assert_eq!(scope.occupied(), 8);
+            assert!(scope.is_live(inside));
+
+            // Scopes nest through the outer one.
+            {
+                let inner = scope.scope();
+                inner.push(3u32).unwrap();
+            }
+
+            assert_eq!(scope.occupied(), 8);
+
+            inside
+        };
+
+        assert_eq!(arena.occupied(), 4);
+        assert!(arena.is_live(outside));
+        assert!(!arena.is_live(generation));
+    }
+
+    #[test]
+    fn test_drop_on_reset() {
+        let dropped = Rc::new(RefCell::new(Vec::new()));
+        let mut arena = Arena::new(1024);
+
+        for i in 0..3 {
+            arena.push(Tracked(i, dropped.clone())).unwrap();
+        }
+
+        assert!(dropped.borrow().is_empty());
+
+        arena.reset();
+
+        assert_eq!(*dropped.borrow(), vec![2, 1, 0]);
+
+        arena.reset();
+
+        assert_eq!(*dropped.borrow(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_drop_on_rewind() {
+        let dropped = Rc::new(RefCell::new(Vec::new()));
+        let mut arena = Arena::new(1024);
+
+        arena.push(Tracked(0, dropped.clone())).unwrap();
+        let checkpoint = arena.checkpoint();
+        arena.push(Tracked(1, dropped.clone())).unwrap();
+        arena.push(Tracked(2, dropped.clone())).unwrap();
+
+        arena.rewind(checkpoint);
+
+        assert_eq!(*dropped.borrow(), vec![2, 1]);
+
+        drop(arena);
+
+        assert_eq!(*dropped.borrow(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_drop_owned_values() {
+        let mut arena = Arena::new(1024);
+        let string = arena.push(String::from("Hello, world!")).unwrap();
+        let vec = arena.push(vec![1, 2, 3]).unwrap();
+
+        assert_eq!(string.as_str(), "Hello, world!");
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+
+        arena.reset();
+
+        assert_eq!(arena.occupied(), 0);
+    }
+}
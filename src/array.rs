@@ -1,18 +1,82 @@
-use super::Arena;
-use std::ops::{Deref, DerefMut};
+use super::{Arena, RawArena};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
+use std::ops::{Bound, Deref, DerefMut, RangeBounds};
 
 /// A fixed-size array that is allocated in an arena.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Array<T> {
-    arena: *const Arena,
+pub struct Array<'a, T, A: RawArena = Arena> {
+    arena: &'a A,
     ptr: *mut T,
     generation: usize,
     len: usize,
     capacity: usize,
 }
 
-impl<T> Array<T> {
-    pub fn new(arena: &Arena, len: usize, capacity: usize) -> Option<Self> {
+/// An iterator that moves a range of elements out of an `Array`.
+pub struct Drain<'d, 'a, T, A: RawArena = Arena> {
+    array: &'d mut Array<'a, T, A>,
+    next: usize,
+    end: usize,
+    tail: usize,
+    tail_len: usize,
+}
+
+/// Resolves `range` into start and end indices within `len` elements.
+fn range_of(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflows"),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflows"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    assert!(start <= end, "range start (is {start}) should be <= range end (is {end})");
+    assert!(end <= len, "range end (is {end}) should be <= len (is {len})");
+
+    (start, end)
+}
+
+/// Sets `len` to `new_len` before dropping `elements`, so a panicking
+/// destructor leaks the rest of them rather than having them dropped twice.
+///
+/// # Safety
+///
+/// `elements` must be initialized and no longer counted by `new_len`.
+pub(crate) unsafe fn forget_and_drop<T, const N: usize>(
+    len: &mut usize,
+    new_len: usize,
+    elements: [*mut [T]; N],
+) {
+    *len = new_len;
+
+    for elements in elements {
+        core::ptr::drop_in_place(elements);
+    }
+}
+
+impl<'a, T, A: RawArena> Array<'a, T, A> {
+    /// Allocates an empty array with room for `capacity` elements.
+    pub fn with_capacity(arena: &'a A, capacity: usize) -> Option<Self> {
+        unsafe { Self::new_with_len(arena, 0, capacity) }
+    }
+
+    /// The first `len` elements are left uninitialized, so a non-zero `len`
+    /// is only sound if they are written before they are read or dropped.
+    #[deprecated(note = "use `with_capacity`, or the unsafe `new_with_len` for a non-zero `len`")]
+    pub fn new(arena: &'a A, len: usize, capacity: usize) -> Option<Self> {
+        unsafe { Self::new_with_len(arena, len, capacity) }
+    }
+
+    /// # Safety
+    ///
+    /// The first `len` elements must be written before they are read or dropped.
+    pub unsafe fn new_with_len(arena: &'a A, len: usize, capacity: usize) -> Option<Self> {
         if capacity < len {
             return None;
         }
@@ -29,52 +93,182 @@ impl<T> Array<T> {
         })
     }
 
-    pub fn from_slice(arena: &Arena, slice: &[T]) -> Option<Self> {
-        let len = slice.len();
-        let mut array = Self::new(arena, 0, len)?;
-
-        array.concat(slice)?;
-
-        Some(array)
-    }
-
     pub fn push(&mut self, value: T) {
         let len = self.len;
 
         if self.capacity > len {
-            let inner = self.deref_mut();
-            inner[len] = value;
+            unsafe { core::ptr::write(self.ptr.add(len), value) };
             self.len += 1;
         }
     }
 
-    pub fn pop(&mut self) -> Option<&T> {
+    pub fn pop(&mut self) -> Option<T> {
         let len = self.len;
 
         if len > 0 {
             self.len -= 1;
-            Some(&self.deref_mut()[len - 1])
+            Some(unsafe { core::ptr::read(self.ptr.add(len - 1)) })
         } else {
             None
         }
     }
 
-    pub fn concat(&mut self, values: &[T]) -> Option<usize> {
-        let len = self.len();
-        let new_len = len + values.len();
+    /// Panics if `index` is greater than the length or the array is full.
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.len;
 
-        if new_len <= self.capacity() {
-            let ptr = self.as_mut_ptr();
+        assert!(index <= len, "insertion index (is {index}) should be <= len (is {len})");
+        assert!(len < self.capacity, "ArenaArray is full");
 
-            unsafe {
-                core::ptr::copy_nonoverlapping(values.as_ptr(), ptr.add(len), values.len());
+        unsafe {
+            let ptr = self.ptr.add(index);
+
+            core::ptr::copy(ptr, ptr.add(1), len - index);
+            core::ptr::write(ptr, value);
+            self.len += 1;
+        }
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len;
+
+        assert!(index < len, "removal index (is {index}) should be < len (is {len})");
+
+        unsafe {
+            let ptr = self.ptr.add(index);
+            let value = core::ptr::read(ptr);
+
+            core::ptr::copy(ptr.add(1), ptr, len - index - 1);
+            self.len -= 1;
+
+            value
+        }
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        let len = self.len;
+
+        assert!(index < len, "swap_remove index (is {index}) should be < len (is {len})");
+
+        unsafe {
+            let value = core::ptr::read(self.ptr.add(index));
+
+            core::ptr::copy(self.ptr.add(len - 1), self.ptr.add(index), 1);
+            self.len -= 1;
+
+            value
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The first `len` elements must be initialized and `len` must not exceed the capacity.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        let old_len = self.len;
+
+        if len >= old_len {
+            return;
+        }
+
+        unsafe {
+            let tail = core::ptr::slice_from_raw_parts_mut(self.ptr.add(len), old_len - len);
+            forget_and_drop(&mut self.len, len, [tail]);
+        }
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let len = self.len;
+        let mut kept = 0;
+
+        // Forget the elements while they are moved around, if `f` panics the
+        // rest of them are leaked rather than dropped twice.
+        self.len = 0;
+
+        unsafe {
+            for i in 0..len {
+                let ptr = self.ptr.add(i);
+
+                if f(&*ptr) {
+                    core::ptr::copy(ptr, self.ptr.add(kept), 1);
+                    kept += 1;
+                } else {
+                    core::ptr::drop_in_place(ptr);
+                }
             }
+        }
 
-            self.len = new_len;
-            Some(new_len)
-        } else {
-            None
+        self.len = kept;
+    }
+
+    /// Removes consecutive elements for which `same_bucket` returns true.
+    pub fn dedup_by(&mut self, mut same_bucket: impl FnMut(&mut T, &mut T) -> bool) {
+        let len = self.len;
+
+        if len < 2 {
+            return;
+        }
+
+        let mut kept = 1;
+
+        // Same as in `retain`, a panic leaks the elements that are left.
+        self.len = 0;
+
+        unsafe {
+            for i in 1..len {
+                let ptr = self.ptr.add(i);
+
+                if same_bucket(&mut *ptr, &mut *self.ptr.add(kept - 1)) {
+                    core::ptr::drop_in_place(ptr);
+                } else {
+                    core::ptr::copy(ptr, self.ptr.add(kept), 1);
+                    kept += 1;
+                }
+            }
+        }
+
+        self.len = kept;
+    }
+
+    /// Panics if the range is out of bounds.
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, 'a, T, A> {
+        let len = self.len;
+        let (start, end) = range_of(range, len);
+
+        // Only the elements before the range belong to the array until the
+        // drain is dropped, so leaking the drain leaks the rest.
+        self.len = start;
+
+        Drain {
+            array: self,
+            next: start,
+            end,
+            tail: end,
+            tail_len: len - end,
+        }
+    }
+
+    /// Panics if `at` is greater than the length.
+    pub fn split_off(&mut self, at: usize) -> Option<Self> {
+        let len = self.len;
+
+        assert!(at <= len, "`at` split index (is {at}) should be <= len (is {len})");
+
+        let mut other = Self::with_capacity(self.arena, len - at)?;
+
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.ptr.add(at), other.ptr, len - at);
         }
+
+        self.len = at;
+        other.len = len - at;
+
+        Some(other)
     }
 
     pub fn len(&self) -> usize {
@@ -90,7 +284,7 @@ impl<T> Array<T> {
     }
 
     pub fn clear(&mut self) {
-        self.len = 0;
+        self.truncate(0);
     }
 
     pub fn as_ptr(&self) -> *const T {
@@ -104,58 +298,286 @@ impl<T> Array<T> {
     pub fn generation(&self) -> usize {
         self.generation
     }
+
+    pub fn arena(&self) -> &'a A {
+        self.arena
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.arena.is_live(self.generation)
+    }
+
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        debug_assert!(self.is_valid(), "ArenaArray used after its arena was reset");
+
+        unsafe {
+            core::slice::from_raw_parts_mut(
+                self.ptr.add(self.len) as *mut MaybeUninit<T>,
+                self.capacity - self.len,
+            )
+        }
+    }
+
+    pub fn try_deref(&self) -> Option<&[T]> {
+        if self.is_valid() {
+            Some(unsafe { core::slice::from_raw_parts(self.ptr, self.len) })
+        } else {
+            None
+        }
+    }
+
+    pub fn try_deref_mut(&mut self) -> Option<&mut [T]> {
+        if self.is_valid() {
+            Some(unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) })
+        } else {
+            None
+        }
+    }
+
+    pub fn try_get(&self, index: usize) -> Option<&T> {
+        self.try_deref().and_then(|slice| slice.get(index))
+    }
+
+    pub fn try_get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.try_deref_mut().and_then(|slice| slice.get_mut(index))
+    }
+}
+
+impl<T> Array<'_, T, Arena> {
+    /// Only works for the most recent allocation of the arena.
+    pub(crate) fn resize_in_place(&mut self, capacity: usize) -> bool {
+        let size = core::mem::size_of::<T>();
+
+        if capacity < self.len {
+            return false;
+        }
+
+        let Some(new_size) = capacity.checked_mul(size) else {
+            return false;
+        };
+
+        let resized = size == 0
+            || self
+                .arena
+                .resize_in_place(self.ptr as *mut u8, self.capacity * size, new_size);
+
+        if resized {
+            self.capacity = capacity;
+        }
+
+        resized
+    }
 }
 
-impl<T> Deref for Array<T> {
+impl<'a, T: Clone, A: RawArena> Array<'a, T, A> {
+    pub fn from_slice(arena: &'a A, slice: &[T]) -> Option<Self> {
+        let len = slice.len();
+        let mut array = Self::with_capacity(arena, len)?;
+
+        array.concat(slice)?;
+
+        Some(array)
+    }
+
+    /// Panics if the range is out of bounds.
+    pub fn extend_from_within(&mut self, range: impl RangeBounds<usize>) -> Option<usize> {
+        let (start, end) = range_of(range, self.len);
+        let new_len = self.len + (end - start);
+
+        if new_len > self.capacity {
+            return None;
+        }
+
+        for i in start..end {
+            let value = unsafe { (*self.ptr.add(i)).clone() };
+            self.push(value);
+        }
+
+        Some(new_len)
+    }
+
+    pub fn resize(&mut self, new_len: usize, value: T) -> Option<usize> {
+        if new_len > self.capacity {
+            return None;
+        }
+
+        self.truncate(new_len);
+
+        while self.len < new_len {
+            self.push(value.clone());
+        }
+
+        Some(new_len)
+    }
+
+    pub fn concat(&mut self, values: &[T]) -> Option<usize> {
+        let len = self.len();
+        let new_len = len + values.len();
+
+        if new_len <= self.capacity() {
+            for value in values {
+                self.push(value.clone());
+            }
+
+            Some(new_len)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, A: RawArena> Deref for Array<'_, T, A> {
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
-        unsafe { core::slice::from_raw_parts(self.ptr, self.capacity) }
+        debug_assert!(self.is_valid(), "ArenaArray used after its arena was reset");
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
     }
 }
 
-impl<T> DerefMut for Array<T> {
+impl<T, A: RawArena> DerefMut for Array<'_, T, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { core::slice::from_raw_parts_mut(self.ptr, self.capacity) }
+        debug_assert!(self.is_valid(), "ArenaArray used after its arena was reset");
+        unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) }
     }
 }
 
-impl<T> AsRef<[T]> for Array<T> {
+impl<T, A: RawArena> AsRef<[T]> for Array<'_, T, A> {
     fn as_ref(&self) -> &[T] {
+        debug_assert!(self.is_valid(), "ArenaArray used after its arena was reset");
         unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
     }
 }
 
-impl<T> AsMut<[T]> for Array<T> {
+impl<T, A: RawArena> AsMut<[T]> for Array<'_, T, A> {
     fn as_mut(&mut self) -> &mut [T] {
+        debug_assert!(self.is_valid(), "ArenaArray used after its arena was reset");
         unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) }
     }
 }
 
-impl<T> Clone for Array<T> {
+impl<T: Clone, A: RawArena> Clone for Array<'_, T, A> {
     fn clone(&self) -> Self {
-        let arena = unsafe { &*self.arena };
-        let new_ptr = arena.push_array(&self[..]).unwrap().as_ptr() as *mut T;
+        let mut array = Array::with_capacity(self.arena, self.capacity).unwrap();
+        array.concat(self.as_ref()).unwrap();
+        array
+    }
+}
+
+unsafe impl<T: Send, A: RawArena + Sync> Send for Array<'_, T, A> {}
+
+unsafe impl<T: Sync, A: RawArena + Sync> Sync for Array<'_, T, A> {}
 
-        Array {
-            arena: self.arena,
-            len: self.len,
-            capacity: self.capacity,
-            generation: self.generation,
-            ptr: new_ptr,
+impl<T, A: RawArena> Drop for Array<'_, T, A> {
+    fn drop(&mut self) {
+        // A stale array no longer owns its elements.
+        if core::mem::needs_drop::<T>() && self.is_valid() {
+            unsafe {
+                core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(self.ptr, self.len));
+            }
         }
     }
 }
 
+impl<T: PartialEq, A: RawArena> Array<'_, T, A> {
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b);
+    }
+}
+
+impl<T, A: RawArena> Iterator for Drain<'_, '_, T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+
+        self.next += 1;
+
+        Some(unsafe { core::ptr::read(self.array.ptr.add(self.next - 1)) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.next;
+
+        (len, Some(len))
+    }
+}
+
+impl<T, A: RawArena> DoubleEndedIterator for Drain<'_, '_, T, A> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.next == self.end {
+            return None;
+        }
+
+        self.end -= 1;
+
+        Some(unsafe { core::ptr::read(self.array.ptr.add(self.end)) })
+    }
+}
+
+impl<T, A: RawArena> ExactSizeIterator for Drain<'_, '_, T, A> {}
+
+impl<T, A: RawArena> Drop for Drain<'_, '_, T, A> {
+    fn drop(&mut self) {
+        let array = &mut *self.array;
+        let start = array.len;
+
+        unsafe {
+            let rest = core::ptr::slice_from_raw_parts_mut(
+                array.ptr.add(self.next),
+                self.end - self.next,
+            );
+
+            // Skip the elements that were not yielded, so that a panicking
+            // destructor cannot make them get dropped twice.
+            self.next = self.end;
+            core::ptr::drop_in_place(rest);
+
+            core::ptr::copy(array.ptr.add(self.tail), array.ptr.add(start), self.tail_len);
+        }
+
+        array.len = start + self.tail_len;
+    }
+}
+
+impl<T: PartialEq, A: RawArena> PartialEq for Array<'_, T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<T: Eq, A: RawArena> Eq for Array<'_, T, A> {}
+
+impl<T: PartialOrd, A: RawArena> PartialOrd for Array<'_, T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_ref().partial_cmp(other.as_ref())
+    }
+}
+
+impl<T: Ord, A: RawArena> Ord for Array<'_, T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_ref().cmp(other.as_ref())
+    }
+}
+
+impl<T: Debug, A: RawArena> Debug for Array<'_, T, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.as_ref()).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Array;
     use crate::Arena;
+    use std::rc::Rc;
 
     #[test]
     fn test_array() {
         let arena = Arena::new(1024);
-        let array: Option<Array<i32>> = Array::new(&arena, 0, 5);
+        let array: Option<Array<i32>> = Array::with_capacity(&arena, 5);
 
         assert!(array.is_some());
 
@@ -168,11 +590,22 @@ mod tests {
     #[test]
     fn test_invalid_array() {
         let arena = Arena::new(1024);
-        let array: Option<Array<i32>> = Array::new(&arena, 5, 0);
+        let array: Option<Array<i32>> = unsafe { Array::new_with_len(&arena, 5, 0) };
 
         assert_eq!(array, None);
     }
 
+    #[test]
+    #[allow(deprecated)]
+    fn test_array_new_with_len_and_capacity() {
+        let arena = Arena::new(1024);
+        let array: Array<i32> = Array::new(&arena, 0, 5).unwrap();
+
+        assert_eq!(array.len(), 0);
+        assert_eq!(array.capacity(), 5);
+        assert!(Array::<i32>::new(&arena, 5, 0).is_none());
+    }
+
     #[test]
     fn test_array_from_slice() {
         let arena = Arena::new(1024);
@@ -190,7 +623,7 @@ mod tests {
     #[test]
     fn test_array_clone() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 5).unwrap();
+        let mut array = Array::with_capacity(&arena, 5).unwrap();
 
         for i in 1..6 {
             array.push(i);
@@ -215,10 +648,22 @@ mod tests {
         assert_eq!(clone[4], 5);
     }
 
+    #[test]
+    fn test_array_eq() {
+        let arena = Arena::new(1024);
+        let array1 = Array::from_slice(&arena, &[1, 2, 3]).unwrap();
+        let array2 = Array::from_slice(&arena, &[1, 2, 3]).unwrap();
+        let array3 = Array::from_slice(&arena, &[1, 2, 4]).unwrap();
+
+        assert_eq!(array1, array2);
+        assert!(array1 < array3);
+        assert_eq!(format!("{:?}", array3), "[1, 2, 4]");
+    }
+
     #[test]
     fn test_array_push() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 10).unwrap();
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
 
         array.push(6);
         array.push(7);
@@ -234,22 +679,46 @@ mod tests {
     #[test]
     fn test_array_pop() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 10).unwrap();
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
 
         array.push(6);
         array.push(7);
         array.push(8);
 
-        assert_eq!(array.pop(), Some(&8));
-        assert_eq!(array.pop(), Some(&7));
-        assert_eq!(array.pop(), Some(&6));
+        assert_eq!(array.last(), Some(&8));
+        assert_eq!(array.pop(), Some(8));
+        assert_eq!(array.pop(), Some(7));
+        assert_eq!(array.pop(), Some(6));
         assert_eq!(array.pop(), None);
+        assert_eq!(array.last(), None);
+    }
+
+    #[test]
+    fn test_array_pop_owned() {
+        let arena = Arena::new(1024);
+        let value = Rc::new(42);
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
+
+        array.push(value.clone());
+        array.push(value.clone());
+
+        let popped = array.pop().unwrap();
+
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        drop(array);
+
+        assert_eq!(Rc::strong_count(&value), 2);
+
+        drop(popped);
+
+        assert_eq!(Rc::strong_count(&value), 1);
     }
 
     #[test]
     fn test_array_clear() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 10).unwrap();
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
 
         array.push(6);
         array.push(7);
@@ -267,7 +736,7 @@ mod tests {
     #[test]
     fn test_array_concat() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 10).unwrap();
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
 
         array.push(6);
         array.push(7);
@@ -298,7 +767,7 @@ mod tests {
     #[test]
     fn test_array_as_ref() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 10).unwrap();
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
 
         array.push(6);
         array.push(7);
@@ -324,7 +793,7 @@ mod tests {
     #[test]
     fn test_array_as_mut() {
         let arena = Arena::new(1024);
-        let mut array = Array::new(&arena, 0, 10).unwrap();
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
 
         array.push(6);
         array.push(7);
@@ -343,4 +812,249 @@ mod tests {
         assert_eq!(array[1], 8);
         assert_eq!(array[2], 9);
     }
+
+    #[test]
+    fn test_array_is_valid() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+        let mut array = Array::from_slice(&arena, &[1, 2, 3]).unwrap();
+
+        assert!(array.is_valid());
+        assert_eq!(array.try_get(1), Some(&2));
+        assert_eq!(array.try_get_mut(2), Some(&mut 3));
+        assert_eq!(array.try_get(3), None);
+        assert_eq!(array.try_deref(), Some(&[1, 2, 3][..]));
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        assert!(!array.is_valid());
+        assert_eq!(array.try_get(1), None);
+        assert_eq!(array.try_get_mut(2), None);
+        assert_eq!(array.try_deref(), None);
+        assert_eq!(array.try_deref_mut(), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ArenaArray used after its arena was reset")]
+    fn test_array_deref_after_reset() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+        let array = Array::from_slice(&arena, &[1, 2, 3]).unwrap();
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        let _ = array[0];
+    }
+
+    #[test]
+    fn test_array_drop() {
+        let arena = Arena::new(1024);
+        let value = Rc::new(42);
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
+
+        for _ in 0..3 {
+            array.push(value.clone());
+        }
+
+        assert_eq!(Rc::strong_count(&value), 4);
+
+        array.clear();
+
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        array.push(value.clone());
+        let clone = array.clone();
+
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        drop(array);
+        drop(clone);
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_array_drop_in_scope() {
+        let mut arena = Arena::new(1024);
+        let value = Rc::new(42);
+
+        {
+            let scope = arena.scope();
+            let array = Array::from_slice(&*scope, &[value.clone(), value.clone()]).unwrap();
+
+            assert_eq!(Rc::strong_count(&value), 3);
+
+            drop(array);
+        }
+
+        // The array dropped its elements, the rewind does not drop them again.
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_array_insert_remove() {
+        let arena = Arena::new(1024);
+        let mut array = Array::with_capacity(&arena, 4).unwrap();
+
+        array.concat(&[1, 2, 3]).unwrap();
+
+        assert_eq!(array.remove(0), 1);
+        assert_eq!(array.swap_remove(0), 2);
+        assert_eq!(array.as_ref(), &[3]);
+
+        array.insert(0, 4);
+        array.insert(2, 5);
+        array.insert(1, 6);
+
+        assert_eq!(array.as_ref(), &[4, 6, 3, 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ArenaArray is full")]
+    fn test_array_insert_full() {
+        let arena = Arena::new(1024);
+        let mut array = Array::from_slice(&arena, &[1, 2, 3]).unwrap();
+
+        array.insert(0, 0);
+    }
+
+    #[test]
+    fn test_array_truncate_retain_dedup() {
+        let value = Rc::new(42);
+        let arena = Arena::new(1024);
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
+
+        for i in [1, 1, 2, 3, 3, 3, 4, 5, 5, 6] {
+            array.push((i, value.clone()));
+        }
+
+        array.dedup_by(|(a, _), (b, _)| a == b);
+
+        assert_eq!(array.as_ref().iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(Rc::strong_count(&value), 7);
+
+        array.retain(|(i, _)| i % 2 == 0);
+        array.truncate(2);
+
+        assert_eq!(array.as_ref().iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        let mut array = Array::from_slice(&arena, &[1, 1, 2, 1]).unwrap();
+        array.dedup();
+
+        assert_eq!(array.as_ref(), &[1, 2, 1]);
+    }
+
+    #[test]
+    fn test_array_drain() {
+        let value = Rc::new(42);
+        let arena = Arena::new(1024);
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
+
+        for i in 0..6 {
+            array.push((i, value.clone()));
+        }
+
+        let mut drain = array.drain(1..4);
+
+        assert_eq!(drain.len(), 3);
+        assert_eq!(drain.next().map(|(i, _)| i), Some(1));
+        assert_eq!(drain.next_back().map(|(i, _)| i), Some(3));
+
+        drop(drain);
+
+        assert_eq!(array.as_ref().iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![0, 4, 5]);
+        assert_eq!(Rc::strong_count(&value), 4);
+
+        let drained: Vec<_> = array.drain(..).map(|(i, _)| i).collect();
+
+        assert_eq!(drained, vec![0, 4, 5]);
+        assert!(array.is_empty());
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_array_split_off() {
+        let arena = Arena::new(1024);
+        let mut array = Array::from_slice(&arena, &[1, 2, 3, 4]).unwrap();
+
+        let tail = array.split_off(1).unwrap();
+
+        assert_eq!(array.as_ref(), &[1]);
+        assert_eq!(tail.as_ref(), &[2, 3, 4]);
+        assert_eq!(tail.capacity(), 3);
+
+        let arena = Arena::new(16);
+        let mut array = Array::from_slice(&arena, &[1, 2, 3, 4]).unwrap();
+
+        assert_eq!(array.split_off(2), None);
+        assert_eq!(array.as_ref(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_array_extend_from_within_resize() {
+        let arena = Arena::new(1024);
+        let mut array = Array::with_capacity(&arena, 8).unwrap();
+
+        array.concat(&[String::from("a"), String::from("b")]).unwrap();
+
+        assert_eq!(array.extend_from_within(..), Some(4));
+        assert_eq!(array.extend_from_within(1..=2), Some(6));
+        assert_eq!(array.extend_from_within(..3), None);
+        assert_eq!(array.as_ref(), &["a", "b", "a", "b", "b", "a"]);
+
+        assert_eq!(array.resize(2, String::from("c")), Some(2));
+        assert_eq!(array.resize(4, String::from("c")), Some(4));
+        assert_eq!(array.resize(9, String::from("c")), None);
+        assert_eq!(array.as_ref(), &["a", "b", "c", "c"]);
+    }
+
+    #[test]
+    fn test_array_deref_len() {
+        let arena = Arena::new(1024);
+        let mut array = Array::with_capacity(&arena, 10).unwrap();
+
+        array.push(6);
+        array.push(7);
+
+        assert_eq!(array.iter().count(), 2);
+        assert_eq!(array.iter_mut().count(), 2);
+        assert_eq!(array.get(2), None);
+        assert_eq!(array.try_get(2), None);
+        assert_eq!(array.try_deref(), Some(&[6, 7][..]));
+
+        array.pop();
+
+        assert_eq!(array.iter().copied().collect::<Vec<_>>(), vec![6]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_array_index_past_len() {
+        let arena = Arena::new(1024);
+        let array = Array::<i32>::with_capacity(&arena, 10).unwrap();
+
+        let _ = array[0];
+    }
+
+    #[test]
+    fn test_array_spare_capacity() {
+        let arena = Arena::new(1024);
+        let mut array = Array::with_capacity(&arena, 4).unwrap();
+
+        array.push(String::from("a"));
+
+        let spare = array.spare_capacity_mut();
+
+        assert_eq!(spare.len(), 3);
+
+        spare[0].write(String::from("b"));
+        spare[1].write(String::from("c"));
+
+        unsafe { array.set_len(3) };
+
+        assert_eq!(array.as_ref(), &["a", "b", "c"]);
+        assert_eq!(array.iter().count(), 3);
+    }
 }
@@ -1,24 +1,92 @@
-use super::Arena;
+use super::{Arena, RawArena, SyncArena};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
 use std::ops::{Deref, DerefMut};
 
 /// A `Box` is a pointer to a value that is allocated in an arena.
 /// Boxed values implement Deref and DerefMut to allow for dereferencing the
-/// pointer to access the value.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Box<T> {
-    arena: *const Arena,
+/// pointer to access the value. The box borrows the arena, so it cannot
+/// outlive it.
+pub struct Box<'a, T, A: RawArena = Arena> {
+    arena: &'a A,
     ptr: *mut T,
     generation: usize,
 }
 
-impl<T> Box<T> {
-    pub fn new(arena: &Arena) -> Option<Self> {
-        arena.alloc::<T>(1).map(|ptr| Box { arena, ptr, generation: arena.generation() })
+impl<'a, T> Box<'a, T> {
+    /// Moves `value` into the arena, which drops it when it is reset,
+    /// rewound past it or dropped.
+    pub fn from_value(arena: &'a Arena, value: T) -> Option<Self> {
+        unsafe { Self::from_value_unchecked(arena, value) }
     }
+}
+
+impl<'a, T, A: RawArena> Box<'a, MaybeUninit<T>, A> {
+    /// Allocates room for a `T` without initializing it. The value is set
+    /// with `write`, or in place followed by `assume_init`.
+    pub fn new(arena: &'a A) -> Option<Self> {
+        arena.alloc::<T>(1).map(|ptr| Box {
+            arena,
+            ptr: ptr as *mut MaybeUninit<T>,
+            generation: arena.generation(),
+        })
+    }
+
+    /// # Safety
+    ///
+    /// The value must have been initialized. The arena does not know about
+    /// it, so it will not be dropped.
+    pub unsafe fn assume_init(self) -> Box<'a, T, A> {
+        Box {
+            arena: self.arena,
+            ptr: self.ptr as *mut T,
+            generation: self.generation,
+        }
+    }
+}
+
+impl<'a, T> Box<'a, MaybeUninit<T>> {
+    /// Initializes the value, which the arena drops when it is reset,
+    /// rewound past it or dropped.
+    pub fn write(self, value: T) -> Box<'a, T> {
+        unsafe {
+            let boxed = self.assume_init();
+
+            core::ptr::write(boxed.ptr, value);
+            boxed.arena.register_drop(boxed.ptr);
+
+            boxed
+        }
+    }
+}
+
+impl<'a, T: Send> Box<'a, MaybeUninit<T>, SyncArena> {
+    /// Initializes the value, which the arena drops when it is reset or
+    /// dropped. That may happen on another thread, hence the `Send` bound.
+    pub fn write(self, value: T) -> Box<'a, T, SyncArena> {
+        unsafe {
+            let boxed = self.assume_init();
+
+            core::ptr::write(boxed.ptr, value);
+            boxed.arena.register_drop(boxed.ptr);
+
+            boxed
+        }
+    }
+}
+
+impl<'a, T, A: RawArena> Box<'a, T, A> {
+    /// # Safety
+    ///
+    /// The arena must be allowed to drop `value` on whatever thread it is
+    /// reset or dropped on.
+    pub(crate) unsafe fn from_value_unchecked(arena: &'a A, value: T) -> Option<Self> {
+        let boxed = Box::<MaybeUninit<T>, A>::new(arena)?.assume_init();
+
+        core::ptr::write(boxed.ptr, value);
+        arena.register_drop(boxed.ptr);
 
-    pub fn from_value(arena: &Arena, value: T) -> Option<Self> {
-        let mut boxed = Self::new(arena)?;
-        *boxed = value;
         Some(boxed)
     }
 
@@ -33,38 +101,92 @@ impl<T> Box<T> {
     pub fn generation(&self) -> usize {
         self.generation
     }
+
+    /// Returns whether the value is still owned by this box, i.e. the arena
+    /// has not been reset or rewound past its allocation.
+    pub fn is_valid(&self) -> bool {
+        self.arena.is_live(self.generation)
+    }
+
+    pub fn try_deref(&self) -> Option<&T> {
+        if self.is_valid() {
+            Some(unsafe { &*self.ptr })
+        } else {
+            None
+        }
+    }
+
+    pub fn try_deref_mut(&mut self) -> Option<&mut T> {
+        if self.is_valid() {
+            Some(unsafe { &mut *self.ptr })
+        } else {
+            None
+        }
+    }
 }
 
-impl<T> Deref for Box<T> {
+impl<T, A: RawArena> Deref for Box<'_, T, A> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
+        debug_assert!(self.is_valid(), "ArenaBox used after its arena was reset");
         unsafe { &*self.ptr }
     }
 }
 
-impl<T> DerefMut for Box<T> {
+impl<T, A: RawArena> DerefMut for Box<'_, T, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        debug_assert!(self.is_valid(), "ArenaBox used after its arena was reset");
         unsafe { &mut *self.ptr }
     }
 }
 
-impl<T> AsRef<T> for Box<T> {
+impl<T, A: RawArena> AsRef<T> for Box<'_, T, A> {
     fn as_ref(&self) -> &T {
         self.deref()
     }
 }
 
-impl<T> AsMut<T> for Box<T> {
+impl<T, A: RawArena> AsMut<T> for Box<'_, T, A> {
     fn as_mut(&mut self) -> &mut T {
         self.deref_mut()
     }
 }
 
-impl<T: Copy> Clone for Box<T> {
+impl<T: Copy, A: RawArena> Clone for Box<'_, T, A> {
     fn clone(&self) -> Self {
-        let arena = unsafe { &*self.arena };
-        arena.push(*self.deref()).unwrap()
+        // Copy types have no destructor for the arena to run.
+        unsafe { Self::from_value_unchecked(self.arena, *self.deref()).unwrap() }
+    }
+}
+
+unsafe impl<T: Send, A: RawArena + Sync> Send for Box<'_, T, A> {}
+
+unsafe impl<T: Sync, A: RawArena + Sync> Sync for Box<'_, T, A> {}
+
+impl<T: PartialEq, A: RawArena> PartialEq for Box<'_, T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref() == other.deref()
+    }
+}
+
+impl<T: Eq, A: RawArena> Eq for Box<'_, T, A> {}
+
+impl<T: PartialOrd, A: RawArena> PartialOrd for Box<'_, T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+
+impl<T: Ord, A: RawArena> Ord for Box<'_, T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deref().cmp(other.deref())
+    }
+}
+
+impl<T: Debug, A: RawArena> Debug for Box<'_, T, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.deref().fmt(f)
     }
 }
 
@@ -150,6 +272,18 @@ mod tests {
         assert_eq!(*boxed, 43);
     }
 
+    #[test]
+    fn test_boxed_eq() {
+        let arena = Arena::new(1024);
+        let boxed1 = arena.push(42).unwrap();
+        let boxed2 = arena.push(42).unwrap();
+        let boxed3 = arena.push(43).unwrap();
+
+        assert_eq!(boxed1, boxed2);
+        assert!(boxed1 < boxed3);
+        assert_eq!(format!("{:?}", boxed3), "43");
+    }
+
     #[test]
     fn test_boxed_clone() {
         let arena = Arena::new(1024);
@@ -165,4 +299,66 @@ mod tests {
         assert_eq!(*cloned, 42);
         assert_eq!(arena.occupied(), core::mem::size_of::<i32>() * 2);
     }
+
+    #[test]
+    fn test_boxed_is_valid() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+        let mut boxed = arena.push(42).unwrap();
+
+        assert!(boxed.is_valid());
+        assert_eq!(boxed.try_deref(), Some(&42));
+        assert_eq!(boxed.try_deref_mut(), Some(&mut 42));
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        assert!(!boxed.is_valid());
+        assert_eq!(boxed.try_deref(), None);
+        assert_eq!(boxed.try_deref_mut(), None);
+    }
+
+    #[test]
+    fn test_boxed_is_valid_after_rewind() {
+        let arena = Arena::new(1024);
+        let before = arena.push(42).unwrap();
+        let checkpoint = arena.checkpoint();
+        let after = arena.push(43).unwrap();
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        assert_eq!(before.try_deref(), Some(&42));
+        assert_eq!(after.try_deref(), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ArenaBox used after its arena was reset")]
+    fn test_boxed_deref_after_reset() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+        let boxed = arena.push(42).unwrap();
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        let _ = *boxed;
+    }
+
+    #[test]
+    fn test_boxed_uninit() {
+        let mut arena = Arena::new(1024);
+
+        // Leave a live `String` behind in the memory the box gets next.
+        arena.push(String::from("Hello, world!")).unwrap();
+        arena.reset();
+
+        let boxed = arena.make_box::<String>().unwrap().write(String::from("foo"));
+
+        assert_eq!(boxed.as_str(), "foo");
+
+        let mut uninit = arena.make_box::<u32>().unwrap();
+        uninit.as_mut().write(42);
+        let boxed = unsafe { uninit.assume_init() };
+
+        assert_eq!(*boxed, 42);
+    }
 }
@@ -0,0 +1,503 @@
+use super::array::forget_and_drop;
+use super::vec::grow;
+use super::{Arena, ArenaArray};
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
+use std::ops::{Index, IndexMut};
+
+/// A double-ended queue that is allocated in an arena, as a ring buffer that
+/// grows like an `ArenaVec`.
+pub struct ArenaDeque<'a, T> {
+    buffer: ArenaArray<'a, MaybeUninit<T>>,
+    head: usize,
+    len: usize,
+}
+
+impl<'a, T> ArenaDeque<'a, T> {
+    pub fn new(arena: &'a Arena) -> Self {
+        Self {
+            buffer: ArenaArray::with_capacity(arena, 0).unwrap(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(arena: &'a Arena, capacity: usize) -> Option<Self> {
+        // The buffer is all uninitialized slots, so its length is its capacity.
+        // They need no initializing, a `MaybeUninit` is never read or dropped.
+        unsafe { ArenaArray::new_with_len(arena, capacity, capacity) }.map(|buffer| Self {
+            buffer,
+            head: 0,
+            len: 0,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn generation(&self) -> usize {
+        self.buffer.generation()
+    }
+
+    pub fn arena(&self) -> &'a Arena {
+        self.buffer.arena()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.buffer.is_valid()
+    }
+
+    fn ptr(&self) -> *mut T {
+        self.buffer.as_ptr() as *mut T
+    }
+
+    fn slot(&self, index: usize) -> usize {
+        let slot = self.head + index;
+
+        if slot >= self.capacity() {
+            slot - self.capacity()
+        } else {
+            slot
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) -> Option<()> {
+        let (len, capacity) = (self.len, self.capacity());
+
+        grow(self, len, capacity, additional, Self::grow_in_place, Self::relocate)
+    }
+
+    fn grow_in_place(&mut self, capacity: usize) -> bool {
+        let old_capacity = self.capacity();
+
+        if !self.buffer.resize_in_place(capacity) {
+            return false;
+        }
+
+        unsafe { self.buffer.set_len(capacity) };
+
+        // If the elements wrap around, move the front part to the end of the
+        // buffer so that the new slots are between the back and the front.
+        if self.head + self.len > old_capacity {
+            let front_len = old_capacity - self.head;
+            let head = capacity - front_len;
+
+            unsafe { core::ptr::copy(self.ptr().add(self.head), self.ptr().add(head), front_len) };
+
+            self.head = head;
+        }
+
+        true
+    }
+
+    fn relocate(&mut self, capacity: usize) -> bool {
+        let Some(mut buffer) = (unsafe { ArenaArray::new_with_len(self.arena(), capacity, capacity) }) else {
+            return false;
+        };
+
+        let (front, back) = self.as_slices();
+        let ptr = buffer.as_mut_ptr() as *mut T;
+
+        unsafe {
+            core::ptr::copy_nonoverlapping(front.as_ptr(), ptr, front.len());
+            core::ptr::copy_nonoverlapping(back.as_ptr(), ptr.add(front.len()), back.len());
+        }
+
+        self.buffer = buffer;
+        self.head = 0;
+
+        true
+    }
+
+    pub fn try_push_back(&mut self, value: T) -> Result<(), T> {
+        if self.reserve(1).is_none() {
+            return Err(value);
+        }
+
+        unsafe { core::ptr::write(self.ptr().add(self.slot(self.len)), value) };
+        self.len += 1;
+
+        Ok(())
+    }
+
+    pub fn try_push_front(&mut self, value: T) -> Result<(), T> {
+        if self.reserve(1).is_none() {
+            return Err(value);
+        }
+
+        self.head = self.slot(self.capacity() - 1);
+        self.len += 1;
+
+        unsafe { core::ptr::write(self.ptr().add(self.head), value) };
+
+        Ok(())
+    }
+
+    /// Panics if the arena has no room left, see `try_push_back`.
+    pub fn push_back(&mut self, value: T) {
+        if self.try_push_back(value).is_err() {
+            panic!("ArenaDeque could not grow");
+        }
+    }
+
+    /// Panics if the arena has no room left, see `try_push_front`.
+    pub fn push_front(&mut self, value: T) {
+        if self.try_push_front(value).is_err() {
+            panic!("ArenaDeque could not grow");
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let value = unsafe { core::ptr::read(self.ptr().add(self.head)) };
+
+        self.head = self.slot(1);
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.len -= 1;
+
+        Some(unsafe { core::ptr::read(self.ptr().add(self.slot(self.len))) })
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        debug_assert!(self.is_valid(), "ArenaDeque used after its arena was reset");
+
+        if index < self.len {
+            Some(unsafe { &*self.ptr().add(self.slot(index)) })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        debug_assert!(self.is_valid(), "ArenaDeque used after its arena was reset");
+
+        if index < self.len {
+            Some(unsafe { &mut *self.ptr().add(self.slot(index)) })
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(self.len.checked_sub(1)?)
+    }
+
+    /// The second slice is only non-empty when the elements wrap around.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        debug_assert!(self.is_valid(), "ArenaDeque used after its arena was reset");
+        let front_len = self.len.min(self.capacity() - self.head);
+
+        unsafe {
+            (
+                core::slice::from_raw_parts(self.ptr().add(self.head), front_len),
+                core::slice::from_raw_parts(self.ptr(), self.len - front_len),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        debug_assert!(self.is_valid(), "ArenaDeque used after its arena was reset");
+        let front_len = self.len.min(self.capacity() - self.head);
+
+        unsafe {
+            (
+                core::slice::from_raw_parts_mut(self.ptr().add(self.head), front_len),
+                core::slice::from_raw_parts_mut(self.ptr(), self.len - front_len),
+            )
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        let (front, back) = self.as_slices();
+
+        front.iter().chain(back)
+    }
+
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T> {
+        let (front, back) = self.as_mut_slices();
+
+        front.iter_mut().chain(back)
+    }
+
+    pub fn clear(&mut self) {
+        let (front, back) = self.as_mut_slices();
+        let (front, back) = (front as *mut [T], back as *mut [T]);
+
+        self.head = 0;
+
+        unsafe { forget_and_drop(&mut self.len, 0, [front, back]) };
+    }
+}
+
+impl<T> Extend<T> for ArenaDeque<'_, T> {
+    /// Panics if the arena has no room left for the values.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let _ = self.reserve(iter.size_hint().0);
+
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> Index<usize> for ArenaDeque<'_, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+
+        self.get(index)
+            .unwrap_or_else(|| panic!("index (is {index}) should be < len (is {len})"))
+    }
+}
+
+impl<T> IndexMut<usize> for ArenaDeque<'_, T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len;
+
+        self.get_mut(index)
+            .unwrap_or_else(|| panic!("index (is {index}) should be < len (is {len})"))
+    }
+}
+
+impl<T> Drop for ArenaDeque<'_, T> {
+    fn drop(&mut self) {
+        // A stale queue no longer owns its elements.
+        if core::mem::needs_drop::<T>() && self.is_valid() {
+            self.clear();
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArenaDeque<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for ArenaDeque<'_, T> {}
+
+impl<T: Debug> Debug for ArenaDeque<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaDeque;
+    use crate::Arena;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_deque_fifo_lifo() {
+        let arena = Arena::new(1024);
+
+        let mut deque = ArenaDeque::new(&arena);
+
+        deque.push_back(1);
+        deque.push_back(2);
+        deque.push_front(0);
+
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.front(), Some(&0));
+        assert_eq!(deque.back(), Some(&2));
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(2));
+        assert_eq!(deque.pop_back(), Some(1));
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+        assert_eq!(deque.back(), None);
+    }
+
+    #[test]
+    fn test_deque_index() {
+        let arena = Arena::new(1024);
+
+        let mut deque = arena.make_deque(4).unwrap();
+
+        deque.extend([2, 3]);
+        deque.push_front(1);
+        deque.push_front(0);
+
+        // The front two elements wrapped around to the end of the buffer.
+        assert_eq!(deque.as_slices(), (&[0, 1][..], &[2, 3][..]));
+
+        deque[1] += 10;
+        *deque.back_mut().unwrap() += 10;
+
+        assert_eq!(deque[1], 11);
+        assert_eq!(deque.get(4), None);
+        assert!(deque.iter().copied().eq([0, 11, 2, 13]));
+        assert!(deque.iter().rev().copied().eq([13, 2, 11, 0]));
+
+        for value in deque.iter_mut() {
+            *value *= 2;
+        }
+
+        assert_eq!(format!("{:?}", deque), "[0, 22, 4, 26]");
+    }
+
+    #[test]
+    #[should_panic(expected = "index (is 2) should be < len (is 2)")]
+    fn test_deque_index_out_of_bounds() {
+        let arena = Arena::new(1024);
+
+        let mut deque = ArenaDeque::new(&arena);
+
+        deque.extend([0, 1]);
+
+        let _ = deque[2];
+    }
+
+    #[test]
+    fn test_deque_grows_in_place() {
+        let arena = Arena::new(1024);
+
+        let mut deque = ArenaDeque::with_capacity(&arena, 4).unwrap();
+
+        deque.extend([1u32, 2, 3, 4]);
+        deque.pop_front();
+        deque.pop_front();
+        deque.extend([5, 6]);
+
+        // The back of the queue has wrapped around to the start of the buffer.
+        let ptr = deque.as_slices().1.as_ptr();
+
+        deque.push_back(7);
+        deque.push_front(2);
+
+        assert_eq!(deque.capacity(), 8);
+        assert_eq!(arena.occupied(), 8 * 4);
+        assert_eq!(deque.as_slices().1.as_ptr(), ptr);
+        assert!(deque.iter().copied().eq(2..8));
+    }
+
+    #[test]
+    fn test_deque_relocates() {
+        let arena = Arena::new(1024);
+
+        let mut deque = ArenaDeque::with_capacity(&arena, 4).unwrap();
+
+        deque.extend([3u32, 4]);
+        deque.push_front(2);
+        deque.push_front(1);
+
+        let other = arena.push(0u32).unwrap();
+
+        deque.push_back(5);
+        deque.push_front(0);
+
+        assert_eq!(deque.capacity(), 8);
+        assert_eq!(deque.as_slices(), (&[0][..], &[1, 2, 3, 4, 5][..]));
+        assert_eq!(*other, 0);
+    }
+
+    #[test]
+    fn test_deque_try_push() {
+        let arena = Arena::new(16);
+
+        let mut deque = ArenaDeque::new(&arena);
+
+        for i in 0..4u32 {
+            assert_eq!(deque.try_push_back(i), Ok(()));
+        }
+
+        assert_eq!(deque.try_push_back(4), Err(4));
+        assert_eq!(deque.try_push_front(4), Err(4));
+        assert!(deque.iter().copied().eq(0..4));
+    }
+
+    #[test]
+    fn test_deque_grows_after_checkpoint() {
+        let arena = Arena::new(1024);
+
+        let mut deque = ArenaDeque::new(&arena);
+
+        arena.checkpoint();
+        deque.push_front(1u32);
+        deque.push_back(2);
+
+        assert!(deque.iter().copied().eq([1, 2]));
+    }
+
+    #[test]
+    fn test_deque_drops() {
+        let value = Rc::new(42);
+        let mut arena = Arena::new(1024);
+
+        {
+            let mut deque = ArenaDeque::new(&arena);
+
+            for _ in 0..3 {
+                deque.push_back(value.clone());
+                deque.push_front(value.clone());
+            }
+
+            let popped = deque.pop_back().unwrap();
+
+            assert_eq!(Rc::strong_count(&value), 7);
+            drop(popped);
+            assert_eq!(Rc::strong_count(&value), 6);
+        }
+
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        arena.reset();
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_deque_clear() {
+        let arena = Arena::new(1024);
+
+        let mut deque = ArenaDeque::new(&arena);
+
+        deque.extend([String::from("b"), String::from("c")]);
+        deque.push_front(String::from("a"));
+        deque.clear();
+
+        assert!(deque.is_empty());
+        assert_eq!(deque.front(), None);
+
+        deque.push_back(String::from("d"));
+
+        assert_eq!(deque.front().map(String::as_str), Some("d"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use super::{Arena, SyncArena};
+
+thread_local! {
+    static ACTIVE: Cell<*const Frame> = const { Cell::new(core::ptr::null()) };
+}
+
+/// The arena that allocations on the current thread are routed to.
+#[derive(Clone, Copy)]
+enum Scope {
+    Arena(*const Arena),
+    Sync(*const SyncArena),
+}
+
+impl Scope {
+    unsafe fn alloc(self, layout: Layout) -> Option<*mut u8> {
+        match self {
+            Scope::Arena(arena) => (*arena).alloc_layout(layout),
+            Scope::Sync(arena) => (*arena).alloc_layout(layout),
+        }
+    }
+
+    unsafe fn contains(self, ptr: *const u8) -> bool {
+        match self {
+            Scope::Arena(arena) => (*arena).contains(ptr),
+            Scope::Sync(arena) => (*arena).contains(ptr),
+        }
+    }
+
+    unsafe fn resize_in_place(self, ptr: *mut u8, old_size: usize, new_size: usize) -> bool {
+        match self {
+            Scope::Arena(arena) => (*arena).resize_in_place(ptr, old_size, new_size),
+            Scope::Sync(_) => false,
+        }
+    }
+}
+
+/// An entry in the per-thread stack of scopes. Frames live on the stack of
+/// `with_scope`, so memory from an inner scope can still be freed while only
+/// an outer scope is active.
+struct Frame {
+    scope: Scope,
+    parent: *const Frame,
+}
+
+impl Frame {
+    /// Finds the innermost frame whose arena owns `ptr`.
+    unsafe fn owner(frame: *const Frame, ptr: *const u8) -> Option<&'static Frame> {
+        let mut frame = frame.as_ref();
+
+        while let Some(current) = frame {
+            if current.scope.contains(ptr) {
+                return Some(current);
+            }
+
+            frame = current.parent.as_ref();
+        }
+
+        None
+    }
+}
+
+/// Restores the enclosing scope when dropped.
+struct ScopeGuard {
+    parent: *const Frame,
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| active.set(self.parent));
+    }
+}
+
+fn with_scope<R>(scope: Scope, f: impl FnOnce() -> R) -> R {
+    let frame = Frame {
+        scope,
+        parent: ACTIVE.with(|active| active.get()),
+    };
+    let _guard = ScopeGuard {
+        parent: frame.parent,
+    };
+
+    ACTIVE.with(|active| active.set(&frame));
+    f()
+}
+
+/// Runs `f` with the scopes taken out, so that allocations the arena itself
+/// makes, such as new chunks, go to the system allocator.
+fn with_active<R>(f: impl FnOnce(*const Frame) -> R) -> R {
+    match ACTIVE.try_with(|active| active.replace(core::ptr::null())) {
+        Ok(frame) => {
+            let result = f(frame);
+            ACTIVE.with(|active| active.set(frame));
+            result
+        }
+        Err(_) => f(core::ptr::null()),
+    }
+}
+
+/// Runs `f` outside of any scope. Arenas use this for their own bookkeeping,
+/// which outlives the scopes they are used in.
+pub(crate) fn unscoped<R>(f: impl FnOnce() -> R) -> R {
+    with_active(|_| f())
+}
+
+/// A global allocator that routes every allocation made on a thread inside
+/// `with_arena` or `with_sync_arena` into that arena, and uses the system
+/// allocator everywhere else or when the arena is full. Install it with
+/// `#[global_allocator]`.
+///
+/// Memory allocated inside a scope is released all at once with the arena,
+/// `with_arena` spells out what that requires of the code in the scope.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScopedAlloc;
+
+unsafe impl GlobalAlloc for ScopedAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        with_active(|frame| {
+            frame
+                .as_ref()
+                .and_then(|frame| frame.scope.alloc(layout))
+                .unwrap_or_else(|| System.alloc(layout))
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        with_active(|frame| match Frame::owner(frame, ptr) {
+            Some(owner) => {
+                owner.scope.resize_in_place(ptr, layout.size(), 0);
+            }
+            None => System.dealloc(ptr, layout),
+        })
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let resized = with_active(|frame| match Frame::owner(frame, ptr) {
+            Some(owner) => owner.scope.resize_in_place(ptr, layout.size(), new_size),
+            None => false,
+        });
+
+        if resized {
+            return ptr;
+        }
+
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = self.alloc(new_layout);
+
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+
+        new_ptr
+    }
+}
+
+/// Routes the allocations `f` makes on the current thread into `arena`.
+///
+/// # Safety
+///
+/// Everything allocated on the current thread while `f` runs must be freed
+/// before `f` returns, or be leaked and never used again. Once no scope for
+/// the arena is active, its memory is handed to the system allocator when
+/// freed. That rules out returning allocations from `f`, storing them in
+/// anything that outlives it, including values pushed into an arena, and
+/// panicking out of it. It also rules out state that is lazily allocated
+/// on first use and kept, such as the buffer behind `println!`.
+pub unsafe fn with_arena<R>(arena: &Arena, f: impl FnOnce() -> R) -> R {
+    with_scope(Scope::Arena(arena), f)
+}
+
+/// Routes the allocations `f` makes on the current thread into `arena`.
+/// Several threads can route their allocations into the same arena.
+///
+/// # Safety
+///
+/// See `with_arena`.
+pub unsafe fn with_sync_arena<R>(arena: &SyncArena, f: impl FnOnce() -> R) -> R {
+    with_scope(Scope::Sync(arena), f)
+}
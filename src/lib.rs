@@ -1,17 +1,33 @@
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
 
+mod allocator;
 mod arena;
 mod array;
 mod boxed;
+mod deque;
+mod global;
 mod list;
+mod map;
+mod pool;
+mod set;
 mod string;
+mod sync;
 mod table;
+mod vec;
 
-pub use arena::Arena;
-pub use array::Array as ArenaArray;
+pub use arena::{Arena, ArenaScope, Checkpoint, RawArena, ResetPolicy};
+pub use array::{Array as ArenaArray, Drain};
 pub use boxed::Box as ArenaBox;
+pub use deque::ArenaDeque;
+pub use global::{with_arena, with_sync_arena, ScopedAlloc};
 pub use list::List as ArenaList;
+pub use map::{ArenaMap, Bucket, InsertError};
+pub use pool::{ArenaPool, PoolGuard, PoolStats};
+pub use set::ArenaSet;
 pub use string::ArenaString;
-pub use table::{ArenaTable, Key};
+pub use sync::SyncArena;
+pub use table::{ArenaTable, Entry, Key, OccupiedEntry, VacantEntry};
+pub use vec::ArenaVec;
 
 #[macro_export]
 macro_rules! arena_alloc {
@@ -72,7 +88,9 @@ macro_rules! arena_table {
     ($arena:expr, $capacity:expr, $values:expr) => {
         $arena.make_table($capacity).and_then(|mut table| {
             for (key, value) in $values.iter() {
-                table.insert(key, *value);
+                if table.insert(key, *value).is_err() {
+                    return None;
+                }
             }
 
             Some(table)
@@ -103,7 +121,7 @@ mod tests {
 
     #[test]
     fn test_arena() {
-        let arena = Arena::new(1024);
+        let mut arena = Arena::new(1024);
 
         assert_eq!(arena.size(), 1024);
         assert_eq!(arena.occupied(), 0);
@@ -140,10 +158,9 @@ mod tests {
     fn test_arena_box() {
         let arena = Arena::new(1024);
         let mut box1: ArenaBox<i32> = arena_alloc!(arena, 0).unwrap();
-        let mut box2: ArenaBox<i32> = arena_alloc!(arena).unwrap();
+        let box2: ArenaBox<i32> = arena_alloc!(arena).unwrap().write(43);
 
         *box1 = 42;
-        *box2 = 43;
 
         assert_eq!(*box1, 42);
         assert_eq!(*box2, 43);
@@ -154,12 +171,15 @@ mod tests {
         let arena = Arena::new(1024);
         let mut array: ArenaArray<i32> = arena_array!(arena, 10).unwrap();
 
+        assert_eq!(array.iter().count(), 0);
+
         for i in 0..10 {
-            array[i] = i as i32;
+            array.push(i);
         }
 
         for i in 0..10 {
-            assert_eq!(array[i], i as i32);
+            array[i] *= 2;
+            assert_eq!(array[i], i as i32 * 2);
         }
     }
 
@@ -191,12 +211,12 @@ mod tests {
         ];
         let mut table = arena_table!(arena, 10).unwrap();
 
-        for i in 0..10 {
-            table.insert(&keys[i], i as u8);
+        for (i, key) in keys.iter().enumerate() {
+            table.insert(key, i as u8).unwrap();
         }
 
-        for i in 0..10 {
-            assert_eq!(table.get(&keys[i]), Some(&(i as u8)));
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(table.get(key), Some(&(i as u8)));
         }
     }
 
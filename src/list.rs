@@ -1,11 +1,13 @@
 use super::Arena;
+use std::fmt::Debug;
 
 /// A singly-linked list that is allocated in an arena. Each push operation
 /// appends a new element to the end of the list by allocating a new node in the
-/// arena.
-#[derive(Debug, PartialEq)]
-pub struct List<T> {
-    arena: *const Arena,
+/// arena. The list borrows the arena, so it cannot outlive it. Popped values
+/// are moved out of their nodes, so like an array's elements the values left
+/// in the list are dropped with it rather than by the arena.
+pub struct List<'a, T> {
+    arena: &'a Arena,
     count: usize,
     generation: usize,
     head: Option<*mut Node<T>>,
@@ -20,8 +22,8 @@ pub struct Node<T> {
 }
 
 
-impl<T> List<T> {
-    pub fn new(arena: &Arena) -> Self {
+impl<'a, T> List<'a, T> {
+    pub fn new(arena: &'a Arena) -> Self {
         let generation = arena.generation();
 
         List {
@@ -41,14 +43,24 @@ impl<T> List<T> {
         self.count == 0
     }
 
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Returns whether the nodes are still owned by this list, i.e. the
+    /// arena has not been reset or rewound past its creation.
+    pub fn is_valid(&self) -> bool {
+        self.arena.is_live(self.generation)
+    }
+
     pub fn push(&mut self, value: T) -> Option<()> {
-        let arena = unsafe { &*self.arena };
-        let mut node = arena.push(Node {
-            next: None,
-            value,
-        })?;
+        debug_assert!(self.is_valid(), "ArenaList used after its arena was reset");
+        let ptr = self.arena.alloc::<Node<T>>(1)?;
 
-        let node_ptr = Some(node.as_mut_ptr());
+        // The node is not registered with the arena, the list drops it.
+        unsafe { core::ptr::write(ptr, Node { next: None, value }) };
+
+        let node_ptr = Some(ptr);
 
         match self.tail {
             None => {
@@ -67,11 +79,14 @@ impl<T> List<T> {
         Some(())
     }
 
-    pub fn pop(&mut self) -> Option<&T> {
+    /// Removes the first element and moves it out of its node. See `peek`
+    /// to look at it without removing it.
+    pub fn pop(&mut self) -> Option<T> {
+        debug_assert!(self.is_valid(), "ArenaList used after its arena was reset");
         match self.head {
             None => None,
             Some(ptr) => {
-                let node = unsafe { &*ptr };
+                let node = unsafe { core::ptr::read(ptr) };
                 self.head = node.next;
                 self.count -= 1;
 
@@ -79,12 +94,28 @@ impl<T> List<T> {
                     self.tail = None;
                 }
 
-                Some(&node.value)
+                Some(node.value)
             }
         }
     }
 
+    /// Returns the first element, which `pop` would remove.
+    pub fn peek(&self) -> Option<&T> {
+        match self.head {
+            None => None,
+            Some(ptr) => unsafe { Some(&(*ptr).value) },
+        }
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        match self.head {
+            None => None,
+            Some(ptr) => unsafe { Some(&mut (*ptr).value) },
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &T> {
+        debug_assert!(self.is_valid(), "ArenaList used after its arena was reset");
         let mut current = self.head;
 
         std::iter::from_fn(move || {
@@ -100,6 +131,7 @@ impl<T> List<T> {
     }
 
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        debug_assert!(self.is_valid(), "ArenaList used after its arena was reset");
         let mut current = self.head;
 
         std::iter::from_fn(move || {
@@ -122,9 +154,31 @@ impl<T> List<T> {
     }
 }
 
+impl<T> Drop for List<'_, T> {
+    fn drop(&mut self) {
+        // A stale list no longer owns its nodes.
+        if core::mem::needs_drop::<T>() && self.is_valid() {
+            while self.pop().is_some() {}
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Debug> Debug for List<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::rc::Rc;
 
     #[test]
     fn test_list() {
@@ -169,10 +223,61 @@ mod tests {
         list.push(43);
         list.push(44);
 
-        assert_eq!(list.pop(), Some(&42));
-        assert_eq!(list.pop(), Some(&43));
-        assert_eq!(list.pop(), Some(&44));
+        assert_eq!(list.peek(), Some(&42));
+        assert_eq!(list.pop(), Some(42));
+        assert_eq!(list.pop(), Some(43));
+
+        *list.peek_mut().unwrap() += 1;
+
+        assert_eq!(list.pop(), Some(45));
         assert_eq!(list.pop(), None);
+        assert_eq!(list.peek(), None);
+    }
+
+    #[test]
+    fn test_list_pop_owned() {
+        let value = Rc::new(42);
+        let mut arena = Arena::new(1024);
+
+        {
+            let mut list = List::new(&arena);
+
+            for _ in 0..3 {
+                list.push(value.clone());
+            }
+
+            let popped = list.pop().unwrap();
+
+            assert_eq!(Rc::strong_count(&value), 4);
+            drop(popped);
+            assert_eq!(Rc::strong_count(&value), 3);
+        }
+
+        // The list dropped what was left, the arena does not drop it again.
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        arena.reset();
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_list_drop_in_scope() {
+        let value = Rc::new(42);
+        let mut arena = Arena::new(1024);
+
+        {
+            let scope = arena.scope();
+            let mut list = List::new(&scope);
+
+            list.push(value.clone());
+            list.push(value.clone());
+
+            assert_eq!(Rc::strong_count(&value), 3);
+        }
+
+        // The list dropped its values, the rewind does not drop them again.
+        assert_eq!(Rc::strong_count(&value), 1);
     }
 
     #[test]
@@ -221,4 +326,36 @@ mod tests {
         assert_eq!(list.tail, None);
         assert_eq!(list.last(), None);
     }
+
+    #[test]
+    fn test_list_is_valid() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+        let mut list = List::new(&arena);
+
+        list.push(42);
+
+        assert!(list.is_valid());
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        assert!(!list.is_valid());
+    }
+
+    #[test]
+    fn test_list_eq() {
+        let arena = Arena::new(1024);
+        let mut list1 = List::new(&arena);
+        let mut list2 = List::new(&arena);
+
+        list1.push(42);
+        list2.push(42);
+
+        assert_eq!(list1, list2);
+
+        list2.push(43);
+
+        assert_ne!(list1, list2);
+        assert_eq!(format!("{:?}", list2), "[42, 43]");
+    }
 }
@@ -0,0 +1,1018 @@
+use super::{Arena, ArenaArray};
+use fxhash::FxBuildHasher;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+/// Marks a slot in the index that has never pointed at an entry.
+const EMPTY: usize = usize::MAX;
+
+/// Marks a slot in the index whose entry was removed. Lookups probe past
+/// it, while inserts may reuse it.
+const TOMBSTONE: usize = usize::MAX - 1;
+
+/// The default share of index slots that may point at entries.
+const DEFAULT_LOAD_FACTOR: f32 = 0.875;
+
+/// Returns the number of index slots needed to hold `capacity` entries
+/// without exceeding `load_factor`. There is always at least one free slot
+/// so that probing ends.
+fn index_slots(capacity: usize, load_factor: f32) -> Option<usize> {
+    let slots = (capacity as f64 / load_factor as f64).ceil() as usize;
+
+    slots.max(capacity.checked_add(1)?).checked_next_power_of_two()
+}
+
+/// An arena backed hash map from any `Hash + Eq` key to a value.
+/// Entries are kept in two arrays, one for the keys and their hashes and
+/// one for the values, and are found through an open-addressing index of
+/// entry positions. Iteration yields them in insertion order. `remove`
+/// moves the last entry into the gap, which is O(1) but reorders it, while
+/// `shift_remove` and `retain` keep the order by shifting the later entries
+/// down. Either way the room is reused by later inserts.
+/// Keys are hashed with fxhash unless another `BuildHasher` is given.
+/// A growable map moves its entries into larger arrays from the same arena
+/// when it is full. The arena cannot take the old arrays back, so they are
+/// left behind and counted by `wasted`.
+pub struct ArenaMap<'a, K, V, S = FxBuildHasher> {
+    buckets: ArenaArray<'a, Bucket<K>>,
+    values: ArenaArray<'a, V>,
+    index: ArenaArray<'a, usize>,
+    tombstones: usize,
+    growable: bool,
+    load_factor: f32,
+    wasted: usize,
+    hash_builder: S,
+}
+
+/// A key stored in an `ArenaMap`, along with its hash.
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Bucket<K> {
+    key: K,
+    hash: usize,
+}
+
+/// The reason `try_insert` did not insert a value, which is handed back to
+/// the caller.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InsertError<V> {
+    /// The key is already in the map.
+    Occupied(V),
+    /// The map or its arena has no room for another entry.
+    Full(V),
+}
+
+impl<K> Bucket<K> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn hash(&self) -> usize {
+        self.hash
+    }
+}
+
+impl<'a, K: Hash + Eq, V> ArenaMap<'a, K, V> {
+    pub fn new(arena: &'a Arena, capacity: usize) -> Option<Self> {
+        Self::with_hasher(arena, capacity, FxBuildHasher::default())
+    }
+}
+
+impl<'a, K: Hash + Eq, V, S: BuildHasher> ArenaMap<'a, K, V, S> {
+    /// Creates a map that hashes its keys with `hash_builder` instead of
+    /// fxhash, for example a `RandomState` when the keys are untrusted.
+    pub fn with_hasher(arena: &'a Arena, capacity: usize, hash_builder: S) -> Option<Self> {
+        let buckets = arena.make_array(capacity)?;
+        let values = arena.make_array(capacity)?;
+        let slots = index_slots(capacity, DEFAULT_LOAD_FACTOR)?;
+        let mut index = ArenaArray::with_capacity(arena, slots)?;
+        index.resize(slots, EMPTY);
+
+        Some(Self {
+            buckets,
+            values,
+            index,
+            tombstones: 0,
+            growable: false,
+            load_factor: DEFAULT_LOAD_FACTOR,
+            wasted: 0,
+            hash_builder,
+        })
+    }
+
+    /// Makes the map grow instead of refusing new keys once it is full.
+    pub fn growable(mut self) -> Self {
+        self.growable = true;
+        self
+    }
+
+    /// Sets the largest share of index slots that may point at entries,
+    /// which trades memory for shorter probes. An empty map resizes its
+    /// index for it right away, and it is used whenever the map grows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `load_factor` is not in `(0, 1]`.
+    pub fn with_load_factor(mut self, load_factor: f32) -> Self {
+        assert!(
+            load_factor > 0.0 && load_factor <= 1.0,
+            "load factor must be in (0, 1], got {load_factor}"
+        );
+
+        self.load_factor = load_factor;
+
+        if self.is_empty() {
+            self.resize_index();
+        }
+
+        self
+    }
+
+    /// Sizes the index of an empty map for its load factor. If the arena has
+    /// no room for a larger index, the map keeps the one it has.
+    fn resize_index(&mut self) {
+        let Some(slots) = index_slots(self.capacity(), self.load_factor) else {
+            return;
+        };
+
+        self.index.clear();
+        self.tombstones = 0;
+
+        // Right after the map is created the index is the most recent
+        // allocation of the arena, so it is usually resized in place.
+        if !self.index.resize_in_place(slots) && slots > self.index.capacity() {
+            if let Some(index) = ArenaArray::with_capacity(self.arena(), slots) {
+                self.wasted += self.index.capacity() * core::mem::size_of::<usize>();
+                self.index = index;
+            }
+        }
+
+        self.index.resize(slots.min(self.index.capacity()), EMPTY);
+    }
+
+    pub fn is_growable(&self) -> bool {
+        self.growable
+    }
+
+    pub fn load_factor(&self) -> f32 {
+        self.load_factor
+    }
+
+    /// Returns the bytes of the arrays that growing has left behind in the
+    /// arena.
+    pub fn wasted(&self) -> usize {
+        self.wasted
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buckets.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.buckets.generation()
+    }
+
+    pub fn arena(&self) -> &'a Arena {
+        self.buckets.arena()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.buckets.is_valid() && self.values.is_valid()
+    }
+
+    pub fn try_get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_valid() {
+            self.get(key)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.hash_builder.hash_one(key) as usize
+    }
+
+    pub fn get_index<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(self.hash(key), key).ok()
+    }
+
+    /// Looks `key` up in the index. Returns the position of its entry, or
+    /// the free slot a new entry for it would go into.
+    pub(crate) fn find<Q>(&self, hash: usize, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let mut tombstone = None;
+
+        for slot in self.probe(hash) {
+            match self.index[slot] {
+                EMPTY => return Err(tombstone.unwrap_or(slot)),
+                TOMBSTONE => {
+                    tombstone.get_or_insert(slot);
+                }
+                // Different keys can share a hash, so compare the keys too.
+                i if self.buckets[i].hash == hash && self.buckets[i].key.borrow() == key => {
+                    return Ok(i)
+                }
+                _ => {}
+            }
+        }
+
+        // There are more slots than entries, so some of them are free.
+        Err(tombstone.expect("the index always has a free slot"))
+    }
+
+    /// Returns the slots of the index in the order they are probed for
+    /// `hash`, starting at its home slot and wrapping around once.
+    fn probe(&self, hash: usize) -> impl Iterator<Item = usize> {
+        let mask = self.index.len() - 1;
+
+        (0..self.index.len()).map(move |i| hash.wrapping_add(i) & mask)
+    }
+
+    /// Returns the slot of the index that points at entry `i`.
+    fn slot_of(&self, i: usize) -> usize {
+        self.probe(self.buckets[i].hash)
+            .find(|&slot| self.index[slot] == i)
+            .expect("every entry is in the index")
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index(key).map(|i| &self.values[i])
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index(key)
+            .map(|i| (&self.buckets[i].key, &self.values[i]))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index(key).map(|i| &mut self.values[i])
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index(key).is_some()
+    }
+
+    /// Inserts `value` under `key`. If the key is already in the map, its
+    /// value is replaced and returned, and the existing key is kept. If the
+    /// key is new and the map has no room for it, the value is handed back.
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, V> {
+        let hash = self.hash(&key);
+
+        match self.find(hash, &key) {
+            Ok(i) => Ok(Some(core::mem::replace(&mut self.values[i], value))),
+            Err(slot) => match self.vacant_slot(hash, slot) {
+                Some(slot) => {
+                    self.push_entry(slot, hash, key, value);
+                    Ok(None)
+                }
+                None => Err(value),
+            },
+        }
+    }
+
+    /// Inserts `value` under `key` unless the key is already in the map.
+    /// On failure the value is handed back inside the error.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<&mut V, InsertError<V>> {
+        let hash = self.hash(&key);
+
+        match self.find(hash, &key) {
+            Ok(_) => Err(InsertError::Occupied(value)),
+            Err(slot) => match self.vacant_slot(hash, slot) {
+                Some(slot) => {
+                    let i = self.push_entry(slot, hash, key, value);
+                    Ok(&mut self.values[i])
+                }
+                None => Err(InsertError::Full(value)),
+            },
+        }
+    }
+
+    /// Makes room for a new entry whose free slot was found to be `slot`,
+    /// growing the map if it is full. Growing rebuilds the index, so the
+    /// slot to use is returned. Returns `None` if there is no room.
+    pub(crate) fn vacant_slot(&mut self, hash: usize, slot: usize) -> Option<usize> {
+        if !self.is_full() {
+            return Some(slot);
+        }
+
+        if !self.growable {
+            return None;
+        }
+
+        self.grow()?;
+
+        self.probe(hash).find(|&slot| self.index[slot] == EMPTY)
+    }
+
+    /// Moves the entries into arrays of twice the capacity and rebuilds the
+    /// index for them.
+    fn grow(&mut self) -> Option<()> {
+        let arena = self.arena();
+        let len = self.len();
+        let capacity = self.capacity().checked_mul(2)?.max(4);
+        let slots = index_slots(capacity, self.load_factor)?;
+
+        let mut buckets = ArenaArray::with_capacity(arena, capacity)?;
+        let mut values = ArenaArray::with_capacity(arena, capacity)?;
+        let mut index = ArenaArray::with_capacity(arena, slots)?;
+        index.resize(slots, EMPTY);
+
+        // Move the entries over, the old arrays must not drop them.
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.buckets.as_ptr(), buckets.as_mut_ptr(), len);
+            core::ptr::copy_nonoverlapping(self.values.as_ptr(), values.as_mut_ptr(), len);
+
+            self.buckets.set_len(0);
+            self.values.set_len(0);
+            buckets.set_len(len);
+            values.set_len(len);
+        }
+
+        self.wasted += self.capacity() * core::mem::size_of::<Bucket<K>>()
+            + self.capacity() * core::mem::size_of::<V>()
+            + self.index.len() * core::mem::size_of::<usize>();
+
+        self.buckets = buckets;
+        self.values = values;
+        self.index = index;
+        self.rebuild_index();
+
+        Some(())
+    }
+
+    /// Appends a new entry and points the free `slot` at it. The map must
+    /// not be full.
+    pub(crate) fn push_entry(&mut self, slot: usize, hash: usize, key: K, value: V) -> usize {
+        let i = self.len();
+
+        if self.index[slot] == TOMBSTONE {
+            self.tombstones -= 1;
+        }
+
+        self.index[slot] = i;
+        self.buckets.push(Bucket { key, hash });
+        self.values.push(value);
+
+        i
+    }
+
+    /// Removes `key` from the map and returns its value, moving the last
+    /// entry into its place. This is O(1) but changes the iteration order,
+    /// `shift_remove` keeps it.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.swap_remove(key)
+    }
+
+    /// Like `remove`, but also hands back the key that was stored.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.swap_remove_entry(key)
+    }
+
+    /// Removes `key` from the map and returns its value. The entries after
+    /// it are shifted down, so the insertion order is kept. That rewrites
+    /// the whole index, so this is O(capacity) rather than O(1).
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.shift_remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Like `shift_remove`, but also hands back the key that was stored.
+    pub fn shift_remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = self.get_index(key)?;
+
+        Some(self.shift_remove_index(i))
+    }
+
+    /// The same as `remove`.
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.swap_remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// The same as `remove_entry`.
+    pub fn swap_remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = self.get_index(key)?;
+
+        Some(self.swap_remove_index(i))
+    }
+
+    /// Keeps only the entries for which `f` returns true, in their order.
+    pub fn retain(&mut self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        let len = self.len();
+        let buckets = self.buckets.as_mut_ptr();
+        let values = self.values.as_mut_ptr();
+        let mut kept = 0;
+
+        // Forget the entries while they are moved around, if `f` panics the
+        // rest of them are leaked rather than dropped twice.
+        self.index.fill(EMPTY);
+        self.tombstones = 0;
+
+        unsafe {
+            self.buckets.set_len(0);
+            self.values.set_len(0);
+
+            for i in 0..len {
+                if f(&(*buckets.add(i)).key, &mut *values.add(i)) {
+                    core::ptr::copy(buckets.add(i), buckets.add(kept), 1);
+                    core::ptr::copy(values.add(i), values.add(kept), 1);
+                    kept += 1;
+                } else {
+                    core::ptr::drop_in_place(buckets.add(i));
+                    core::ptr::drop_in_place(values.add(i));
+                }
+            }
+
+            self.buckets.set_len(kept);
+            self.values.set_len(kept);
+        }
+
+        self.rebuild_index();
+    }
+
+    /// Sorts the entries by key. The sort is stable, and lookups keep
+    /// working as the index is rebuilt for the new order.
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.sort_by(|k1, _, k2, _| k1.cmp(k2));
+    }
+
+    /// Sorts the entries with `cmp`, keeping the order of equal entries.
+    pub fn sort_by(&mut self, mut cmp: impl FnMut(&K, &V, &K, &V) -> Ordering) {
+        self.sort_entries(|order, buckets, values| {
+            order.sort_by(|&a, &b| cmp(&buckets[a].key, &values[a], &buckets[b].key, &values[b]));
+        });
+    }
+
+    /// Sorts the entries by the key `f` extracts from them. The sort is
+    /// unstable, so equal entries may end up in any order.
+    pub fn sort_unstable_by_key<T: Ord>(&mut self, mut f: impl FnMut(&K, &V) -> T) {
+        self.sort_entries(|order, buckets, values| {
+            order.sort_unstable_by_key(|&i| f(&buckets[i].key, &values[i]));
+        });
+    }
+
+    /// Reorders the entries in place. `sort` gets the positions of the
+    /// entries to put in order, which are kept in the index since it has
+    /// more slots than there are entries and is rebuilt afterwards anyway.
+    fn sort_entries(&mut self, sort: impl FnOnce(&mut [usize], &[Bucket<K>], &[V])) {
+        let len = self.len();
+        let guard = IndexGuard(self);
+        let map = &mut *guard.0;
+        let order = &mut map.index.as_mut()[..len];
+
+        for (i, position) in order.iter_mut().enumerate() {
+            *position = i;
+        }
+
+        sort(order, map.buckets.as_ref(), map.values.as_ref());
+
+        // Entry `order[i]` belongs at `i`, follow each cycle of the
+        // permutation and mark the positions that are done.
+        for i in 0..len {
+            let mut j = i;
+
+            loop {
+                let k = order[j];
+                order[j] = j;
+
+                if k == i {
+                    break;
+                }
+
+                map.buckets.as_mut().swap(j, k);
+                map.values.as_mut().swap(j, k);
+                j = k;
+            }
+        }
+    }
+
+    pub(crate) fn shift_remove_index(&mut self, i: usize) -> (K, V) {
+        self.forget_slot(i);
+
+        // Every entry after the removed one moves down by one.
+        for slot in self.index.iter_mut() {
+            if *slot > i && *slot < TOMBSTONE {
+                *slot -= 1;
+            }
+        }
+
+        let bucket = self.buckets.remove(i);
+        let value = self.values.remove(i);
+
+        self.compact_index();
+
+        (bucket.key, value)
+    }
+
+    pub(crate) fn swap_remove_index(&mut self, i: usize) -> (K, V) {
+        let last = self.len() - 1;
+
+        self.forget_slot(i);
+
+        if i != last {
+            let slot = self.slot_of(last);
+            self.index[slot] = i;
+        }
+
+        let bucket = self.buckets.swap_remove(i);
+        let value = self.values.swap_remove(i);
+
+        self.compact_index();
+
+        (bucket.key, value)
+    }
+
+    /// Leaves a tombstone in the slot of entry `i`, so that probing for the
+    /// keys placed after it still gets past it.
+    fn forget_slot(&mut self, i: usize) {
+        let slot = self.slot_of(i);
+
+        self.index[slot] = TOMBSTONE;
+        self.tombstones += 1;
+    }
+
+    /// Clears out the tombstones once they make up a quarter of the index,
+    /// so that lookups for missing keys do not have to wade through them.
+    fn compact_index(&mut self) {
+        if self.tombstones > self.index.len() / 4 {
+            self.rebuild_index();
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index.fill(EMPTY);
+        self.tombstones = 0;
+
+        for i in 0..self.len() {
+            let slot = self
+                .probe(self.buckets[i].hash)
+                .find(|&slot| self.index[slot] == EMPTY)
+                .unwrap();
+
+            self.index[slot] = i;
+        }
+    }
+
+    pub(crate) fn buckets(&self) -> &ArenaArray<'a, Bucket<K>> {
+        &self.buckets
+    }
+
+    pub(crate) fn value_array(&self) -> &ArenaArray<'a, V> {
+        &self.values
+    }
+
+    pub(crate) fn value_mut(&mut self, i: usize) -> &mut V {
+        &mut self.values[i]
+    }
+
+    /// Returns the entries as slices, with the values borrowed mutably.
+    pub(crate) fn value_slices_mut(&mut self) -> (&[Bucket<K>], &mut [V]) {
+        (self.buckets.as_ref(), self.values.as_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.buckets.as_ref().iter().map(|bucket| &bucket.key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.values.as_ref().iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.values.as_mut().iter_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.keys().zip(self.values.as_ref())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        let (buckets, values) = self.value_slices_mut();
+
+        buckets.iter().map(|bucket| &bucket.key).zip(values)
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.values.clear();
+        self.index.fill(EMPTY);
+        self.tombstones = 0;
+    }
+}
+
+/// Rebuilds the index of a map when dropped, so that the map stays usable
+/// even if sorting its entries panics halfway.
+struct IndexGuard<'m, 'a, K: Hash + Eq, V, S: BuildHasher>(&'m mut ArenaMap<'a, K, V, S>);
+
+impl<K: Hash + Eq, V, S: BuildHasher> Drop for IndexGuard<'_, '_, K, V, S> {
+    fn drop(&mut self) {
+        self.0.rebuild_index();
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for ArenaMap<'_, K, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for ArenaMap<'_, K, V, S> {}
+
+impl<K: Hash + Ord, V: PartialOrd, S: BuildHasher> PartialOrd for ArenaMap<'_, K, V, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K: Hash + Ord, V: Ord, S: BuildHasher> Ord for ArenaMap<'_, K, V, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<K: Hash + Eq + Debug, V: Debug, S: BuildHasher> Debug for ArenaMap<'_, K, V, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArenaMap, InsertError};
+    use crate::Arena;
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasherDefault, Hasher};
+    use std::rc::Rc;
+
+    /// A hasher that sends every key to the same slot.
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    #[test]
+    fn test_map_integer_keys() {
+        let arena = Arena::new(1024 * 64);
+
+        let mut map = ArenaMap::<u64, u64>::new(&arena, 1000).unwrap();
+
+        for i in 0..1000 {
+            assert_eq!(map.insert(i * 7, i), Ok(None));
+        }
+
+        for i in 0..1000 {
+            assert_eq!(map.get(&(i * 7)), Some(&i));
+        }
+
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.insert(7, 100), Ok(Some(1)));
+        assert_eq!(map.len(), 1000);
+    }
+
+    #[test]
+    fn test_map_tuple_keys() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<(u32, char), &str>::new(&arena, 4).unwrap();
+
+        map.insert((1, 'a'), "one a").unwrap();
+        map.insert((1, 'b'), "one b").unwrap();
+        map.insert((2, 'a'), "two a").unwrap();
+
+        assert_eq!(map.get(&(1, 'b')), Some(&"one b"));
+        assert_eq!(map.get(&(2, 'b')), None);
+        assert_eq!(map.get_key_value(&(2, 'a')), Some((&(2, 'a'), &"two a")));
+    }
+
+    #[test]
+    fn test_map_borrowed_lookups() {
+        let arena = Arena::new(4096);
+
+        let mut strings = ArenaMap::<String, u32>::new(&arena, 4).unwrap();
+        let mut bytes = ArenaMap::<Vec<u8>, u32>::new(&arena, 4).unwrap();
+
+        strings.insert("foo".to_string(), 1).unwrap();
+        bytes.insert(b"bar".to_vec(), 2).unwrap();
+
+        assert_eq!(strings.get("foo"), Some(&1));
+        assert!(strings.contains_key("foo"));
+        assert_eq!(bytes.get(&b"bar"[..]), Some(&2));
+        assert_eq!(bytes.remove(&b"bar"[..]), Some(2));
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_map_arena_string_keys() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::new(&arena, 4).unwrap();
+
+        map.insert(arena.push_string("foo").unwrap(), 1).unwrap();
+        map.insert(arena.push_string("bar").unwrap(), 2).unwrap();
+
+        assert_eq!(map.get("foo"), Some(&1));
+        assert_eq!(map.get("bar"), Some(&2));
+        assert_eq!(map.get("baz"), None);
+    }
+
+    #[test]
+    fn test_map_try_insert() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<u32, u32>::new(&arena, 1).unwrap();
+
+        assert_eq!(map.try_insert(1, 10), Ok(&mut 10));
+        assert_eq!(map.try_insert(1, 11), Err(InsertError::Occupied(11)));
+        assert_eq!(map.try_insert(2, 20), Err(InsertError::Full(20)));
+    }
+
+    #[test]
+    fn test_map_collisions() {
+        let arena = Arena::new(4096);
+        let hasher = BuildHasherDefault::<Collide>::default();
+
+        let mut map = ArenaMap::<u32, u32, _>::with_hasher(&arena, 8, hasher).unwrap();
+
+        for i in 0..8 {
+            map.insert(i, i * 10).unwrap();
+        }
+
+        assert_eq!(map.remove(&3), Some(30));
+        assert_eq!(map.swap_remove(&0), Some(0));
+
+        for i in [1, 2, 4, 5, 6, 7] {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+
+        assert!(!map.contains_key(&0));
+        assert!(!map.contains_key(&3));
+    }
+
+    #[test]
+    fn test_map_drops_keys_and_values() {
+        let arena = Arena::new(4096);
+        let key = Rc::new(());
+        let value = Rc::new(());
+
+        {
+            let mut map = ArenaMap::new(&arena, 4).unwrap();
+
+            map.insert(Rc::new(1), key.clone()).unwrap();
+            map.insert(Rc::new(2), value.clone()).unwrap();
+            map.insert(Rc::new(3), value.clone()).unwrap();
+
+            map.retain(|k, _| **k != 3);
+
+            assert_eq!(Rc::strong_count(&value), 2);
+        }
+
+        assert_eq!(Rc::strong_count(&key), 1);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_map_random_state() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::with_hasher(&arena, 16, RandomState::new()).unwrap();
+
+        for i in 0..16u32 {
+            map.insert(i, i * 2).unwrap();
+        }
+
+        assert!((0..16).all(|i| map.get(&i) == Some(&(i * 2))));
+        assert!(map.iter().map(|(key, _)| *key).eq(0..16));
+    }
+
+    #[test]
+    fn test_map_sort() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::new(&arena, 64).unwrap();
+
+        // A permutation of 0..64 with cycles of several lengths.
+        for i in 0..64u32 {
+            map.insert((i * 37) % 64, i).unwrap();
+        }
+
+        map.sort_keys();
+
+        assert!(map.keys().copied().eq(0..64));
+        assert!((0..64).all(|i| map.get(&((i * 37) % 64)) == Some(&i)));
+
+        map.sort_unstable_by_key(|_, value| *value);
+
+        assert!(map.values().copied().eq(0..64));
+        assert_eq!(map.get_index(&37), Some(1));
+    }
+
+    #[test]
+    fn test_map_sort_panic() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::new(&arena, 8).unwrap();
+
+        for i in 0..8 {
+            map.insert(i, i * 2).unwrap();
+        }
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.sort_by(|_, _, _, _| panic!("no order"));
+        }));
+
+        assert!(result.is_err());
+        assert!((0..8).all(|i| map.get(&i) == Some(&(i * 2))));
+        assert_eq!(map.get(&8), None);
+    }
+
+    #[test]
+    fn test_map_growable() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<u64, u64>::new(&arena, 2).unwrap().growable();
+
+        assert!(map.is_growable());
+
+        for i in 0..5 {
+            assert_eq!(map.try_insert(i, i * 10), Ok(&mut (i * 10)));
+        }
+
+        assert_eq!(map.capacity(), 8);
+        assert!(map.iter().map(|(key, value)| (*key, *value)).eq((0..5).map(|i| (i, i * 10))));
+
+        // Two generations of 16 byte buckets, 8 byte values and the index.
+        assert_eq!(map.wasted(), (2 * 24 + 4 * 8) + (4 * 24 + 8 * 8));
+    }
+
+    #[test]
+    fn test_map_fixed_does_not_grow() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<u64, u64>::new(&arena, 2).unwrap();
+
+        map.insert(1, 1).unwrap();
+        map.insert(2, 2).unwrap();
+
+        assert_eq!(map.try_insert(3, 3), Err(InsertError::Full(3)));
+        assert_eq!(map.capacity(), 2);
+        assert_eq!(map.wasted(), 0);
+    }
+
+    #[test]
+    fn test_map_load_factor() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<u32, u32>::new(&arena, 0)
+            .unwrap()
+            .growable()
+            .with_load_factor(0.5);
+
+        map.insert(1, 1).unwrap();
+
+        assert_eq!(map.capacity(), 4);
+        assert_eq!(map.index.len(), 8);
+
+        for i in 2..6 {
+            map.insert(i, i).unwrap();
+        }
+
+        assert_eq!(map.capacity(), 8);
+        assert_eq!(map.index.len(), 16);
+        assert!((1..6).all(|i| map.get(&i) == Some(&i)));
+    }
+
+    #[test]
+    fn test_map_fixed_load_factor() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<u32, u32>::new(&arena, 7).unwrap().with_load_factor(0.5);
+
+        assert_eq!(map.index.len(), 16);
+
+        map.insert(1, 1).unwrap();
+
+        // The index of a map that is not empty keeps its size.
+        let map = map.with_load_factor(1.0);
+
+        assert_eq!(map.index.len(), 16);
+        assert_eq!(map.get(&1), Some(&1));
+
+        let map = ArenaMap::<u32, u32>::new(&arena, 15).unwrap().with_load_factor(1.0);
+
+        assert_eq!(map.index.len(), 16);
+        assert_eq!(map.wasted(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "load factor must be in (0, 1]")]
+    fn test_map_invalid_load_factor() {
+        let arena = Arena::new(4096);
+
+        ArenaMap::<u32, u32>::new(&arena, 4).unwrap().with_load_factor(1.5);
+    }
+
+    #[test]
+    fn test_map_grows_after_checkpoint() {
+        let arena = Arena::new(4096);
+
+        let mut map = ArenaMap::<u64, u64>::new(&arena, 2).unwrap().growable();
+
+        map.insert(1, 1).unwrap();
+        map.insert(2, 2).unwrap();
+        arena.checkpoint();
+
+        assert_eq!(map.insert(3, 3), Ok(None));
+        assert_eq!(map.capacity(), 4);
+        assert_eq!(map.get(&3), Some(&3));
+    }
+
+    #[test]
+    fn test_map_grow_out_of_arena() {
+        let arena = Arena::new(512);
+
+        let mut map = ArenaMap::<u64, u64>::new(&arena, 1).unwrap().growable();
+        let mut inserted = 0;
+
+        while map.try_insert(inserted, inserted).is_ok() {
+            inserted += 1;
+        }
+
+        assert!(inserted > 1);
+        assert!((0..inserted).all(|i| map.get(&i) == Some(&i)));
+    }
+}
@@ -0,0 +1,493 @@
+use std::cell::RefCell;
+use std::ops::Deref;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::Arena;
+
+static NEXT_POOL_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static LOCAL: RefCell<Vec<LocalSlot>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A fast path slot holding at most one idle arena. The slot is shared by a
+/// pool and one thread, the thread swaps arenas in and out of it without
+/// locking and the pool empties it when it is dropped.
+struct Slot {
+    arena: AtomicPtr<Arena>,
+    held: Arc<AtomicUsize>,
+}
+
+// Only arenas that have been reset are parked in a slot, so they hold no
+// values that would be dropped on the wrong thread.
+unsafe impl Send for Slot {}
+unsafe impl Sync for Slot {}
+
+impl Slot {
+    fn new(held: Arc<AtomicUsize>) -> Slot {
+        Slot {
+            arena: AtomicPtr::new(ptr::null_mut()),
+            held,
+        }
+    }
+
+    fn take(&self) -> Option<Box<Arena>> {
+        let arena = self.arena.swap(ptr::null_mut(), Ordering::Acquire);
+
+        (!arena.is_null()).then(|| unsafe { Box::from_raw(arena) })
+    }
+
+    fn put(&self, arena: Box<Arena>) -> Result<(), Box<Arena>> {
+        let arena = Box::into_raw(arena);
+
+        self.arena
+            .compare_exchange(ptr::null_mut(), arena, Ordering::Release, Ordering::Relaxed)
+            .map(|_| ())
+            .map_err(|_| unsafe { Box::from_raw(arena) })
+    }
+
+    /// Frees the parked arena and stops counting it against the pool.
+    fn clear(&self) {
+        if let Some(arena) = self.take() {
+            self.held.fetch_sub(arena.size(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// A thread's handle to its slot for a given pool.
+struct LocalSlot {
+    pool: usize,
+    slot: Arc<Slot>,
+}
+
+impl LocalSlot {
+    /// Returns whether the pool is gone and the thread is left as the only
+    /// owner of the slot.
+    fn is_stale(&self) -> bool {
+        Arc::strong_count(&self.slot) == 1
+    }
+}
+
+impl Drop for LocalSlot {
+    fn drop(&mut self) {
+        // The thread is exiting or the pool is gone, nobody would take the
+        // arena out of the slot anymore.
+        self.slot.clear();
+    }
+}
+
+/// An idle arena in the shared free list. Pooled arenas have been reset, so
+/// they hold no values that would be dropped on the wrong thread.
+struct SharedArena(Box<Arena>);
+
+unsafe impl Send for SharedArena {}
+
+/// Statistics about how an `ArenaPool` has been used.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Acquisitions served by a recycled arena.
+    pub hits: usize,
+    /// Acquisitions that had to allocate a new arena.
+    pub misses: usize,
+    /// Arenas currently handed out.
+    pub in_use: usize,
+    /// The largest number of arenas handed out at once.
+    pub peak_in_use: usize,
+    /// The largest number of bytes occupied in an arena when it was released.
+    pub peak_occupied: usize,
+    /// Bytes held by the pool, in arenas handed out as well as idle ones.
+    pub held: usize,
+}
+
+/// A pool of arenas for per-request scratch memory. Each thread keeps the
+/// last arena it released in a thread-local slot, so acquiring on the same
+/// thread again does not touch any lock. Other idle arenas are kept in a
+/// shared free list. The arenas handed out and the idle ones never hold more
+/// than `max_held` bytes in total, acquiring fails once that is reached.
+pub struct ArenaPool {
+    id: usize,
+    arena_size: usize,
+    max_held: usize,
+    held: Arc<AtomicUsize>,
+    shared: Mutex<Vec<SharedArena>>,
+    slots: Mutex<Vec<Arc<Slot>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    in_use: AtomicUsize,
+    peak_in_use: AtomicUsize,
+    peak_occupied: AtomicUsize,
+}
+
+/// A request-scoped arena handed out by an `ArenaPool`. The arena is reset
+/// and returned to the pool when the guard is dropped.
+pub struct PoolGuard<'a> {
+    pool: &'a ArenaPool,
+    arena: Option<Box<Arena>>,
+}
+
+impl ArenaPool {
+    pub fn new(arena_size: usize, max_held: usize) -> ArenaPool {
+        ArenaPool {
+            id: NEXT_POOL_ID.fetch_add(1, Ordering::Relaxed),
+            arena_size,
+            max_held,
+            held: Arc::new(AtomicUsize::new(0)),
+            shared: Mutex::new(Vec::new()),
+            slots: Mutex::new(Vec::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            in_use: AtomicUsize::new(0),
+            peak_in_use: AtomicUsize::new(0),
+            peak_occupied: AtomicUsize::new(0),
+        }
+    }
+
+    /// Hands out an idle arena, or a new one if the pool has room for it.
+    /// Returns `None` when every arena the pool may hold is handed out.
+    pub fn acquire(&self) -> Option<PoolGuard<'_>> {
+        let arena = match self.take_local().or_else(|| self.take_shared()) {
+            Some(arena) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                arena
+            }
+            None if self.reserve() => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Box::new(Arena::new(self.arena_size))
+            }
+            // The pool is at its limit, but idle arenas may still be parked
+            // in the slots of other threads.
+            None => {
+                let arena = self.take_parked()?;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                arena
+            }
+        };
+
+        let in_use = self.in_use.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_in_use.fetch_max(in_use, Ordering::Relaxed);
+
+        Some(PoolGuard {
+            pool: self,
+            arena: Some(arena),
+        })
+    }
+
+    /// Counts a new arena against the limit before it is allocated, so that
+    /// threads racing for the last room cannot both get it.
+    fn reserve(&self) -> bool {
+        self.held
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |held| {
+                held.checked_add(self.arena_size).filter(|&held| held <= self.max_held)
+            })
+            .is_ok()
+    }
+
+    /// Runs `f` on the thread's slots, after dropping the ones of pools that
+    /// are gone.
+    fn with_local<R>(&self, f: impl FnOnce(&mut Vec<LocalSlot>) -> R) -> Option<R> {
+        LOCAL
+            .try_with(|local| {
+                let mut local = local.borrow_mut();
+                local.retain(|cached| !cached.is_stale());
+                f(&mut local)
+            })
+            .ok()
+    }
+
+    fn take_local(&self) -> Option<Box<Arena>> {
+        self.with_local(|local| {
+            local
+                .iter()
+                .find(|cached| cached.pool == self.id)?
+                .slot
+                .take()
+        })
+        .flatten()
+    }
+
+    fn take_shared(&self) -> Option<Box<Arena>> {
+        self.shared.lock().unwrap().pop().map(|shared| shared.0)
+    }
+
+    fn take_parked(&self) -> Option<Box<Arena>> {
+        self.slots.lock().unwrap().iter().find_map(|slot| slot.take())
+    }
+
+    fn local_slot(&self, local: &mut Vec<LocalSlot>) -> Arc<Slot> {
+        if let Some(cached) = local.iter().find(|cached| cached.pool == self.id) {
+            return cached.slot.clone();
+        }
+
+        let slot = Arc::new(Slot::new(self.held.clone()));
+        let mut slots = self.slots.lock().unwrap();
+
+        // Threads that exited have dropped their handles and emptied their
+        // slots, so the pool is the only owner left.
+        slots.retain(|slot| Arc::strong_count(slot) > 1);
+        slots.push(slot.clone());
+
+        local.push(LocalSlot {
+            pool: self.id,
+            slot: slot.clone(),
+        });
+
+        slot
+    }
+
+    fn release(&self, mut arena: Box<Arena>) {
+        self.peak_occupied.fetch_max(arena.occupied(), Ordering::Relaxed);
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+
+        arena.reset();
+
+        let mut arena = Some(arena);
+
+        self.with_local(|local| {
+            if let Err(rejected) = self.local_slot(local).put(arena.take().unwrap()) {
+                arena = Some(rejected);
+            }
+        });
+
+        // The thread already parked an arena for this pool, or it is exiting.
+        if let Some(arena) = arena {
+            self.shared.lock().unwrap().push(SharedArena(arena));
+        }
+    }
+
+    pub fn arena_size(&self) -> usize {
+        self.arena_size
+    }
+
+    pub fn max_held(&self) -> usize {
+        self.max_held
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            peak_in_use: self.peak_in_use.load(Ordering::Relaxed),
+            peak_occupied: self.peak_occupied.load(Ordering::Relaxed),
+            held: self.held.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for ArenaPool {
+    fn drop(&mut self) {
+        // Free the arenas parked by every thread, not only the current one.
+        // The threads drop their handles to the empty slots the next time
+        // they use a pool or when they exit.
+        for slot in self.slots.get_mut().unwrap().iter() {
+            slot.clear();
+        }
+    }
+}
+
+impl Deref for PoolGuard<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        self.arena.as_deref().unwrap()
+    }
+}
+
+impl Drop for PoolGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(arena) = self.arena.take() {
+            self.pool.release(arena);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArenaPool, LOCAL};
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn test_pool_recycles_arenas() {
+        let pool = ArenaPool::new(1024, 4096);
+
+        let first = {
+            let arena = pool.acquire().unwrap();
+            arena.push(42u32).unwrap().as_ptr()
+        };
+
+        let arena = pool.acquire().unwrap();
+
+        assert_eq!(arena.occupied(), 0);
+        assert_eq!(arena.push(43u32).unwrap().as_ptr(), first);
+
+        let stats = pool.stats();
+
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.in_use, 1);
+        assert_eq!(stats.held, 1024);
+    }
+
+    #[test]
+    fn test_pool_shared_arenas() {
+        let pool = ArenaPool::new(1024, 4096);
+
+        {
+            let _first = pool.acquire().unwrap();
+            let _second = pool.acquire().unwrap();
+            let _third = pool.acquire().unwrap();
+        }
+
+        let stats = pool.stats();
+
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.peak_in_use, 3);
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.held, 3072);
+
+        let _first = pool.acquire().unwrap();
+        let _second = pool.acquire().unwrap();
+        let _third = pool.acquire().unwrap();
+        let _fourth = pool.acquire().unwrap();
+
+        let stats = pool.stats();
+
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 4);
+        assert_eq!(stats.held, 4096);
+    }
+
+    #[test]
+    fn test_pool_max_held() {
+        let pool = ArenaPool::new(1024, 2048);
+
+        {
+            let guards: Vec<_> = (0..2).map(|_| pool.acquire().unwrap()).collect();
+
+            // Arenas handed out count against the limit as well.
+            assert!(pool.acquire().is_none());
+            assert_eq!(guards.len(), 2);
+            assert_eq!(pool.stats().held, 2048);
+        }
+
+        assert_eq!(pool.stats().held, 2048);
+        assert!(pool.acquire().is_some());
+
+        let pool = ArenaPool::new(1024, 0);
+
+        assert!(pool.acquire().is_none());
+
+        let stats = pool.stats();
+
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 0);
+        assert_eq!(stats.held, 0);
+    }
+
+    #[test]
+    fn test_pool_takes_parked_arenas() {
+        let pool = ArenaPool::new(1024, 1024);
+        let parked = Barrier::new(2);
+        let done = Barrier::new(2);
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                drop(pool.acquire().unwrap());
+                parked.wait();
+                done.wait();
+            });
+
+            parked.wait();
+
+            // The only arena is idle in the other thread's slot.
+            assert!(pool.acquire().is_some());
+            assert_eq!(pool.stats().hits, 1);
+
+            done.wait();
+        });
+    }
+
+    #[test]
+    fn test_pool_drop_frees_parked_arenas() {
+        let pool = Arc::new(ArenaPool::new(1024, 4096));
+        let parked = Arc::new(Barrier::new(2));
+        let dropped = Arc::new(Barrier::new(2));
+
+        let handle = thread::spawn({
+            let (pool, parked, dropped) = (pool.clone(), parked.clone(), dropped.clone());
+
+            move || {
+                drop(pool.acquire().unwrap());
+                drop(pool);
+                parked.wait();
+                dropped.wait();
+
+                let freed = LOCAL.with(|local| {
+                    let local = local.borrow();
+                    local.len() == 1 && local[0].slot.arena.load(Ordering::Relaxed).is_null()
+                });
+
+                // Using another pool drops the handle to the stale slot.
+                let other = ArenaPool::new(1024, 4096);
+                drop(other.acquire().unwrap());
+
+                (freed, LOCAL.with(|local| local.borrow().len()))
+            }
+        });
+
+        parked.wait();
+        drop(pool);
+        dropped.wait();
+
+        assert_eq!(handle.join().unwrap(), (true, 1));
+    }
+
+    #[test]
+    fn test_pool_peak_occupied() {
+        let pool = ArenaPool::new(1024, 4096);
+
+        {
+            let arena = pool.acquire().unwrap();
+            arena.make_array::<u8>(100).unwrap();
+        }
+
+        {
+            let arena = pool.acquire().unwrap();
+            arena.make_array::<u8>(10).unwrap();
+        }
+
+        assert_eq!(pool.stats().peak_occupied, 100);
+    }
+
+    #[test]
+    fn test_pool_threads() {
+        let pool = ArenaPool::new(1024, 1024 * 16);
+
+        thread::scope(|scope| {
+            for t in 0..8u32 {
+                let pool = &pool;
+
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        let arena = pool.acquire().unwrap();
+                        let boxed = arena.push(t * 100 + i).unwrap();
+
+                        assert_eq!(*boxed, t * 100 + i);
+                        assert_eq!(arena.occupied(), 4);
+                    }
+                });
+            }
+        });
+
+        let stats = pool.stats();
+
+        assert_eq!(stats.hits + stats.misses, 800);
+        assert_eq!(stats.in_use, 0);
+        assert!(stats.misses <= 8);
+        assert!(stats.held <= pool.max_held());
+    }
+}
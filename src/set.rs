@@ -0,0 +1,297 @@
+use super::map::ArenaMap;
+use super::Arena;
+use fxhash::FxBuildHasher;
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+/// An arena backed hash set. This is an `ArenaMap` with `()` values, which
+/// take up no room in the arena, so it shares the map's hashing, growth
+/// and insertion-order iteration.
+pub struct ArenaSet<'a, T, S = FxBuildHasher> {
+    map: ArenaMap<'a, T, (), S>,
+}
+
+impl<'a, T: Hash + Eq> ArenaSet<'a, T> {
+    pub fn new(arena: &'a Arena, capacity: usize) -> Option<Self> {
+        ArenaMap::new(arena, capacity).map(|map| Self { map })
+    }
+}
+
+impl<'a, T: Hash + Eq, S: BuildHasher> ArenaSet<'a, T, S> {
+    /// Creates a set that hashes its values with `hash_builder` instead of
+    /// fxhash.
+    pub fn with_hasher(arena: &'a Arena, capacity: usize, hash_builder: S) -> Option<Self> {
+        ArenaMap::with_hasher(arena, capacity, hash_builder).map(|map| Self { map })
+    }
+
+    /// Makes the set grow instead of refusing new values once it is full.
+    pub fn growable(self) -> Self {
+        Self {
+            map: self.map.growable(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.map.generation()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.map.is_valid()
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(value)
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(value).map(|(value, _)| value)
+    }
+
+    /// Adds `value` to the set. Returns whether it was not in the set yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is new and the set is full, see `try_insert`
+    /// for a fallible alternative.
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.try_insert(value) {
+            Ok(inserted) => inserted,
+            Err(_) => panic!("ArenaSet is full"),
+        }
+    }
+
+    /// Adds `value` to the set. Returns whether it was not in the set yet,
+    /// or hands the value back if the set has no room for it.
+    pub fn try_insert(&mut self, value: T) -> Result<bool, T> {
+        let hash = self.map.hash(&value);
+
+        match self.map.find(hash, &value) {
+            Ok(_) => Ok(false),
+            Err(slot) => match self.map.vacant_slot(hash, slot) {
+                Some(slot) => {
+                    self.map.push_entry(slot, hash, value, ());
+                    Ok(true)
+                }
+                None => Err(value),
+            },
+        }
+    }
+
+    /// Removes `value` from the set, moving the last value into its place.
+    /// Returns whether it was in the set.
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(value).is_some()
+    }
+
+    /// Removes `value` from the set, keeping the order of the others. This
+    /// is O(capacity), see `ArenaMap::shift_remove`.
+    pub fn shift_remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.shift_remove(value).is_some()
+    }
+
+    /// Removes `value` from the set and returns the stored value.
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove_entry(value).map(|(value, _)| value)
+    }
+
+    /// Keeps only the values for which `f` returns true, in their order.
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.map.retain(|value, _| f(value))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.map.keys()
+    }
+
+    /// Returns the values that are in `self` or `other`, those of `self`
+    /// first.
+    pub fn union<'b>(&'b self, other: &'b ArenaSet<'a, T, S>) -> impl Iterator<Item = &'b T> {
+        self.iter().chain(other.difference(self))
+    }
+
+    /// Returns the values of `self` that are also in `other`.
+    pub fn intersection<'b>(
+        &'b self,
+        other: &'b ArenaSet<'a, T, S>,
+    ) -> impl Iterator<Item = &'b T> {
+        self.iter().filter(move |value| other.contains(*value))
+    }
+
+    /// Returns the values of `self` that are not in `other`.
+    pub fn difference<'b>(
+        &'b self,
+        other: &'b ArenaSet<'a, T, S>,
+    ) -> impl Iterator<Item = &'b T> {
+        self.iter().filter(move |value| !other.contains(*value))
+    }
+
+    pub fn is_subset(&self, other: &ArenaSet<'a, T, S>) -> bool {
+        self.len() <= other.len() && self.iter().all(|value| other.contains(value))
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> PartialEq for ArenaSet<'_, T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|value| other.contains(value))
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Eq for ArenaSet<'_, T, S> {}
+
+impl<T: Hash + Eq + Debug, S: BuildHasher> Debug for ArenaSet<'_, T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaSet;
+    use crate::Arena;
+
+    fn set_of<'a>(arena: &'a Arena, values: &[u32]) -> ArenaSet<'a, u32> {
+        let mut set = ArenaSet::new(arena, values.len()).unwrap();
+
+        for value in values {
+            set.insert(*value);
+        }
+
+        set
+    }
+
+    #[test]
+    fn test_set_insert() {
+        let arena = Arena::new(1024);
+
+        let mut set = ArenaSet::new(&arena, 4).unwrap();
+
+        assert!(set.insert(1));
+        assert!(set.insert(2));
+        assert!(!set.insert(1));
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&1));
+        assert!(!set.contains(&3));
+        assert_eq!(set.get(&2), Some(&2));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_set_try_insert() {
+        let arena = Arena::new(1024);
+
+        let mut set = ArenaSet::new(&arena, 1).unwrap();
+
+        assert_eq!(set.try_insert(1), Ok(true));
+        assert_eq!(set.try_insert(1), Ok(false));
+        assert_eq!(set.try_insert(2), Err(2));
+
+        let mut set = ArenaSet::new(&arena, 1).unwrap().growable();
+
+        assert_eq!(set.try_insert(1), Ok(true));
+        assert_eq!(set.try_insert(2), Ok(true));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_set_no_values_array() {
+        let arena = Arena::new(1024);
+
+        ArenaSet::<u64>::new(&arena, 8).unwrap();
+
+        // Buckets of a value and its hash plus a 16 slot index.
+        assert_eq!(arena.occupied(), 8 * 16 + 16 * 8);
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let arena = Arena::new(1024);
+
+        let mut set = set_of(&arena, &[1, 2, 3, 4]);
+
+        assert!(set.remove(&2));
+        assert!(!set.remove(&2));
+        assert_eq!(set.take(&3), Some(3));
+        assert_eq!(set.take(&3), None);
+
+        set.retain(|value| *value != 4);
+
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let arena = Arena::new(4096);
+
+        let a = set_of(&arena, &[1, 2, 3, 4]);
+        let b = set_of(&arena, &[3, 4, 5]);
+
+        assert_eq!(a.union(&b).copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(a.intersection(&b).copied().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(a.difference(&b).copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(b.difference(&a).copied().collect::<Vec<_>>(), vec![5]);
+
+        assert!(set_of(&arena, &[4, 3]).is_subset(&a));
+        assert!(!b.is_subset(&a));
+    }
+
+    #[test]
+    fn test_set_eq() {
+        let arena = Arena::new(4096);
+
+        assert_eq!(set_of(&arena, &[1, 2, 3]), set_of(&arena, &[3, 2, 1]));
+        assert_ne!(set_of(&arena, &[1, 2]), set_of(&arena, &[1, 2, 3]));
+        assert_eq!(format!("{:?}", set_of(&arena, &[1, 2])), "{1, 2}");
+    }
+
+    #[test]
+    fn test_set_str() {
+        let arena = Arena::new(4096);
+
+        let mut set = ArenaSet::new(&arena, 4).unwrap();
+
+        set.insert(arena.push_string("foo").unwrap());
+        set.insert(arena.push_string("bar").unwrap());
+
+        assert!(set.contains("foo"));
+        assert!(!set.contains("baz"));
+        assert!(set.remove("bar"));
+    }
+}
@@ -1,4 +1,6 @@
 use super::{Arena, ArenaArray};
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
 use std::fmt::{Debug, Display};
 use std::cmp::Ordering;
 use std::fmt::Write;
@@ -8,11 +10,11 @@ use std::ops::Deref;
 /// This is a zero-copy string, and is not null-terminated.
 /// ArenaString derefs to a `str` and can be used in most places where a `str` is expected.
 #[derive(Clone, Eq)]
-pub struct ArenaString {
-    inner: ArenaArray<u8>,
+pub struct ArenaString<'a> {
+    inner: ArenaArray<'a, u8>,
 }
 
-impl Deref for ArenaString {
+impl Deref for ArenaString<'_> {
     type Target = str;
 
     fn deref(&self) -> &Self::Target {
@@ -20,37 +22,50 @@ impl Deref for ArenaString {
     }
 }
 
-impl AsRef<str> for ArenaString {
+impl Borrow<str> for ArenaString<'_> {
+    fn borrow(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+impl Hash for ArenaString<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Must match the hash of `str` for lookups through `Borrow<str>`.
+        self.as_ref().hash(state)
+    }
+}
+
+impl AsRef<str> for ArenaString<'_> {
     fn as_ref(&self) -> &str {
-        unsafe { std::str::from_utf8_unchecked(&self.inner[0..self.inner.len()]) }
+        self
     }
 }
 
-impl PartialEq for ArenaString {
+impl PartialEq for ArenaString<'_> {
     fn eq(&self, other: &Self) -> bool {
-        self.deref()[0..self.len()] == other.deref()[0..other.len()]
+        self.as_str() == other.as_str()
     }
 }
 
-impl PartialOrd for ArenaString {
+impl PartialOrd for ArenaString<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for ArenaString {
+impl Ord for ArenaString<'_> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.deref()[0..self.len()].cmp(&other.deref()[0..other.len()])
+        self.as_str().cmp(other.as_str())
     }
 }
 
-impl PartialEq<str> for ArenaString {
+impl PartialEq<str> for ArenaString<'_> {
     fn eq(&self, other: &str) -> bool {
-        &self.deref()[0..self.len()] == other
+        self.as_str() == other
     }
 }
 
-impl Write for ArenaString {
+impl Write for ArenaString<'_> {
     fn write_str(&mut self, s: &str) -> std::fmt::Result {
         match self.concat(s) {
             Some(_) => Ok(()),
@@ -59,32 +74,32 @@ impl Write for ArenaString {
     }
 }
 
-impl Debug for ArenaString {
+impl Debug for ArenaString<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{:?}", self.as_ref())
     }
 }
 
-impl Display for ArenaString {
+impl Display for ArenaString<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.as_ref())
     }
 }
 
-impl ArenaString {
-    pub fn new(arena: &Arena, capacity: usize) -> Option<Self> {
-        ArenaArray::new(arena, 0, capacity).map(|inner| ArenaString { inner })
+impl<'a> ArenaString<'a> {
+    pub fn new(arena: &'a Arena, capacity: usize) -> Option<Self> {
+        ArenaArray::with_capacity(arena, capacity).map(|inner| ArenaString { inner })
     }
 
-    pub fn from_slice(arena: &Arena, slice: &[u8]) -> Option<Self> {
+    pub fn from_slice(arena: &'a Arena, slice: &[u8]) -> Option<Self> {
         ArenaArray::from_slice(arena, slice).map(|inner| ArenaString { inner })
     }
 
-    pub fn from_str(arena: &Arena, str: &str) -> Option<Self> {
+    pub fn from_str(arena: &'a Arena, str: &str) -> Option<Self> {
         ArenaArray::from_slice(arena, str.as_bytes()).map(|inner| ArenaString { inner })
     }
 
-    pub fn from_array(inner: ArenaArray<u8>) -> Self {
+    pub fn from_array(inner: ArenaArray<'a, u8>) -> Self {
         ArenaString { inner }
     }
 
@@ -104,6 +119,16 @@ impl ArenaString {
         self.inner.generation()
     }
 
+    pub fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+
+    pub fn try_deref(&self) -> Option<&str> {
+        self.inner
+            .try_deref()
+            .map(|bytes| unsafe { std::str::from_utf8_unchecked(bytes) })
+    }
+
     pub fn clear(&mut self) {
         self.inner.clear();
     }
@@ -130,19 +155,19 @@ mod tests {
 
         assert_eq!(string.len(), 0);
         assert_eq!(string.capacity(), 1024);
-        assert_eq!(string.is_empty(), true);
+        assert!(string.is_empty());
 
         let _ = write!(&mut string, "Hello, world!");
 
         assert_eq!(string.len(), 13);
         assert_eq!(&string, "Hello, world!");
-        assert_eq!(string.is_empty(), false);
+        assert!(!string.is_empty());
 
         string.clear();
 
         assert_eq!(string.len(), 0);
         assert_eq!(string.capacity(), 1024);
-        assert_eq!(string.is_empty(), true);
+        assert!(string.is_empty());
     }
 
     #[test]
@@ -173,8 +198,8 @@ mod tests {
         assert_eq!(string.len(), 0);
         assert_eq!(string.capacity(), 1024);
 
-        let _ = write!(&mut string, "Hello, ").unwrap();
-        let _ = write!(&mut string, "world!").unwrap();
+        write!(&mut string, "Hello, ").unwrap();
+        write!(&mut string, "world!").unwrap();
 
         assert_eq!(string.len(), 13);
         assert_eq!(&string, "Hello, world!");
@@ -239,6 +264,18 @@ mod tests {
         assert_eq!(&string.as_ref()[0..string.len()], "Hello, world!");
     }
 
+    #[test]
+    fn test_arena_string_deref_len() {
+        let arena = Arena::new(1024);
+        let mut string = arena.make_string(128).unwrap();
+
+        let _ = write!(&mut string, "Hello");
+
+        assert_eq!(&*string, "Hello");
+        assert_eq!(string.chars().count(), 5);
+        assert_eq!(string.try_deref(), Some("Hello"));
+    }
+
     #[test]
     fn test_arena_string_from_slice() {
         let arena = Arena::new(1024);
@@ -256,7 +293,7 @@ mod tests {
 
         let _ = write!(&mut string, "Hello, world!");
 
-        assert_eq!(&string == "Hello, world!", true);
+        assert!(&string == "Hello, world!");
     }
 
     #[test]
@@ -280,8 +317,8 @@ mod tests {
         let arena = Arena::new(1024);
         let mut string = arena.make_string(20).unwrap();
 
-        assert_eq!(write!(&mut string, "Hello, world!").is_ok(), true);
-        assert_eq!(write!(&mut string, "Hello, world!").is_err(), true);
+        assert!(write!(&mut string, "Hello, world!").is_ok());
+        assert!(write!(&mut string, "Hello, world!").is_err());
     }
 
     #[test]
@@ -304,4 +341,19 @@ mod tests {
         assert_eq!(string.len(), 13);
         assert_eq!(&string, "Hello, world!");
     }
+
+    #[test]
+    fn test_arena_string_is_valid() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+        let string = arena.push_string("Hello, world!").unwrap();
+
+        assert!(string.is_valid());
+        assert_eq!(string.try_deref(), Some("Hello, world!"));
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        assert!(!string.is_valid());
+        assert_eq!(string.try_deref(), None);
+    }
 }
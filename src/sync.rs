@@ -0,0 +1,365 @@
+use std::alloc::Layout;
+use std::boxed::Box;
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::arena::DropEntry;
+use super::global::unscoped;
+use super::{ArenaArray, ArenaBox, RawArena};
+
+/// A fixed size arena that can be shared between threads. Allocations bump
+/// the offset with an atomic compare-and-swap, so they never take a lock.
+/// Boxes and arrays allocated in a `SyncArena` are `Send` and `Sync`
+/// whenever their contents are.
+#[derive(Debug)]
+pub struct SyncArena {
+    // Kept as a raw pointer rather than a `Box`, so the regions handed out
+    // are not derived from a shared reference to the buffer.
+    data: NonNull<u8>,
+    size: usize,
+    offset: AtomicUsize,
+    generation: AtomicUsize,
+    drops: Mutex<Vec<DropEntry>>,
+}
+
+// Only `Send` values are registered to be dropped, and the buffer is only
+// ever handed out in disjoint regions.
+unsafe impl Send for SyncArena {}
+unsafe impl Sync for SyncArena {}
+
+impl SyncArena {
+    pub fn new(size: usize) -> SyncArena {
+        let data = Box::into_raw(vec![0u8; size].into_boxed_slice());
+
+        SyncArena {
+            data: unsafe { NonNull::new_unchecked(data as *mut u8) },
+            size,
+            offset: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
+            drops: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn alloc<T>(&self, len: usize) -> Option<*mut T> {
+        let layout = Layout::array::<T>(len).ok()?;
+
+        self.alloc_layout(layout).map(|ptr| ptr as *mut T)
+    }
+
+    /// Allocates a block of memory that fits `layout`. Zero-sized requests
+    /// do not take up any space and get a dangling, well-aligned pointer.
+    pub fn alloc_layout(&self, layout: Layout) -> Option<*mut u8> {
+        if layout.size() == 0 {
+            return Some(layout.align() as *mut u8);
+        }
+
+        let (size, align) = (layout.size(), layout.align());
+        let base = self.data.as_ptr() as usize;
+        let mut current = self.offset.load(Ordering::Relaxed);
+
+        loop {
+            // The buffer is only byte aligned, so align the actual address.
+            let start = (base + current).checked_add(align - 1)? & !(align - 1);
+            let offset = start - base;
+
+            if offset > self.size() || size > self.size() - offset {
+                return None;
+            }
+
+            // Regions never overlap, so there is nothing to synchronize with
+            // besides the offset itself.
+            match self.offset.compare_exchange_weak(
+                current,
+                offset + size,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(unsafe { self.data.as_ptr().add(offset) }),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Returns whether `ptr` points into the arena's buffer.
+    pub(crate) fn contains(&self, ptr: *const u8) -> bool {
+        let base = self.data.as_ptr() as *const u8;
+
+        (base..base.wrapping_add(self.size)).contains(&ptr)
+    }
+
+    /// Registers `ptr` to be dropped in place when the arena is reset or
+    /// dropped. Does nothing for types without drop glue.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to an initialized `T` allocated from this arena that
+    /// is not dropped by anyone else, and `T` must be safe to drop on another
+    /// thread.
+    pub unsafe fn register_drop<T>(&self, ptr: *mut T) {
+        if core::mem::needs_drop::<T>() {
+            unscoped(|| self.drops.lock().unwrap().push(DropEntry::new(ptr)));
+        }
+    }
+
+    fn run_drops(&mut self) {
+        let entries = core::mem::take(self.drops.get_mut().unwrap());
+
+        for entry in entries.into_iter().rev() {
+            unsafe { entry.run() };
+        }
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns whether memory handed out while the arena was at `generation`
+    /// has not been released by a reset since.
+    pub fn is_live(&self, generation: usize) -> bool {
+        generation == self.generation()
+    }
+
+    pub fn make_box<T>(&self) -> Option<ArenaBox<'_, MaybeUninit<T>, SyncArena>> {
+        ArenaBox::new(self)
+    }
+
+    pub fn make_array<T>(&self, capacity: usize) -> Option<ArenaArray<'_, T, SyncArena>> {
+        ArenaArray::with_capacity(self, capacity)
+    }
+
+    /// Moves `value` into the arena, which drops it when it is reset or
+    /// dropped. That may happen on another thread, hence the `Send` bound.
+    pub fn push<T: Send>(&self, value: T) -> Option<ArenaBox<'_, T, SyncArena>> {
+        unsafe { ArenaBox::from_value_unchecked(self, value) }
+    }
+
+    pub fn push_array<T: Clone>(&self, values: &[T]) -> Option<ArenaArray<'_, T, SyncArena>> {
+        ArenaArray::from_slice(self, values)
+    }
+
+    pub fn reset(&mut self) {
+        self.run_drops();
+
+        if *self.offset.get_mut() > 0 {
+            *self.generation.get_mut() += 1;
+        }
+
+        *self.offset.get_mut() = 0;
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.offset.load(Ordering::Relaxed)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied() == self.size()
+    }
+}
+
+unsafe impl RawArena for SyncArena {
+    fn alloc<T>(&self, len: usize) -> Option<*mut T> {
+        SyncArena::alloc(self, len)
+    }
+
+    fn generation(&self) -> usize {
+        SyncArena::generation(self)
+    }
+
+    fn is_live(&self, generation: usize) -> bool {
+        SyncArena::is_live(self, generation)
+    }
+
+    unsafe fn register_drop<T>(&self, ptr: *mut T) {
+        SyncArena::register_drop(self, ptr)
+    }
+}
+
+impl Drop for SyncArena {
+    fn drop(&mut self) {
+        self.run_drops();
+
+        let data = core::ptr::slice_from_raw_parts_mut(self.data.as_ptr(), self.size);
+        drop(unsafe { Box::from_raw(data) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SyncArena;
+    use crate::{ArenaArray, ArenaBox};
+    use std::alloc::Layout;
+    use std::sync::Arc;
+    use std::thread;
+
+    // The stress tests cannot tell whether the regions handed out may be
+    // written through, run them under Miri for that. They do fewer
+    // iterations there to stay fast enough.
+    const THREADS: usize = 8;
+    const ITERATIONS: usize = if cfg!(miri) { 20 } else { 500 };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_sync_arena() {
+        let mut arena = SyncArena::new(1024);
+
+        {
+            let boxed = arena.push(42u32).unwrap();
+            let array = arena.push_array(&[1u16, 2, 3]).unwrap();
+
+            assert_eq!(*boxed, 42);
+            assert_eq!(array.as_ref(), &[1, 2, 3]);
+            assert_eq!(arena.occupied(), 10);
+        }
+
+        arena.reset();
+
+        assert_eq!(arena.occupied(), 0);
+        assert_eq!(arena.generation(), 1);
+    }
+
+    #[test]
+    fn test_sync_arena_alloc_layout() {
+        let arena = SyncArena::new(256);
+
+        arena.push(1u8).unwrap();
+
+        let ptr = arena.alloc_layout(Layout::from_size_align(16, 64).unwrap()).unwrap();
+        let unit = arena.alloc::<()>(usize::MAX).unwrap();
+
+        assert_eq!(ptr as usize % 64, 0);
+        assert_eq!(unit as usize, 1);
+        assert!(arena.alloc::<u64>(usize::MAX).is_none());
+        assert!(arena.alloc_layout(Layout::from_size_align(512, 1).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_sync_arena_write() {
+        let arena = SyncArena::new(256);
+
+        let first = arena.alloc::<u64>(4).unwrap();
+        let second = arena.alloc::<u64>(4).unwrap();
+
+        unsafe {
+            for i in 0..4 {
+                first.add(i).write(i as u64);
+                second.add(i).write(i as u64 + 4);
+            }
+
+            assert!((0..4).all(|i| *first.add(i) == i as u64));
+            assert!((0..4).all(|i| *second.add(i) == i as u64 + 4));
+        }
+    }
+
+    #[test]
+    fn test_sync_arena_send_sync() {
+        assert_send_sync::<SyncArena>();
+        assert_send_sync::<ArenaBox<'static, u32, SyncArena>>();
+        assert_send_sync::<ArenaArray<'static, String, SyncArena>>();
+    }
+
+    #[test]
+    fn test_sync_arena_concurrent_push() {
+        let arena = SyncArena::new(THREADS * ITERATIONS * 24);
+
+        let mut regions: Vec<(usize, usize)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let arena = &arena;
+
+                    scope.spawn(move || {
+                        let mut boxes = Vec::new();
+                        let mut arrays = Vec::new();
+
+                        for i in 0..ITERATIONS {
+                            let value = (t * ITERATIONS + i) as u64;
+                            boxes.push(arena.push(value).unwrap());
+                            arrays.push(arena.push_array(&[t as u32; 4]).unwrap());
+                        }
+
+                        (t, boxes, arrays)
+                    })
+                })
+                .collect();
+
+            let mut regions = Vec::new();
+
+            for handle in handles {
+                let (t, boxes, arrays) = handle.join().unwrap();
+
+                for (i, boxed) in boxes.iter().enumerate() {
+                    assert_eq!(**boxed, (t * ITERATIONS + i) as u64);
+                    regions.push((boxed.as_ptr() as usize, 8));
+                }
+
+                for array in arrays.iter() {
+                    assert_eq!(array.as_ref(), &[t as u32; 4]);
+                    regions.push((array.as_ptr() as usize, 16));
+                }
+            }
+
+            regions
+        });
+
+        assert_eq!(arena.occupied(), THREADS * ITERATIONS * 24);
+        assert!(arena.is_full());
+
+        regions.sort();
+
+        for pair in regions.windows(2) {
+            assert!(pair[0].0 + pair[0].1 <= pair[1].0);
+        }
+    }
+
+    #[test]
+    fn test_sync_arena_concurrent_exhaustion() {
+        let fits = THREADS * ITERATIONS / 4;
+        let arena = SyncArena::new(fits * 8);
+
+        let allocated: usize = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let arena = &arena;
+                    scope.spawn(move || {
+                        (0..ITERATIONS)
+                            .filter(|_| arena.alloc::<u64>(1).is_some())
+                            .count()
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).sum()
+        });
+
+        assert_eq!(allocated, fits);
+        assert!(arena.is_full());
+    }
+
+    #[test]
+    fn test_sync_arena_drop() {
+        let value = Arc::new(42);
+        let mut arena = SyncArena::new(1024);
+
+        thread::scope(|scope| {
+            for _ in 0..THREADS {
+                let arena = &arena;
+                let value = value.clone();
+                scope.spawn(move || {
+                    arena.push(value).unwrap();
+                });
+            }
+        });
+
+        assert_eq!(Arc::strong_count(&value), THREADS + 1);
+
+        arena.reset();
+
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
@@ -1,135 +1,486 @@
+use super::map::{ArenaMap, Bucket, InsertError};
 use super::{Arena, ArenaArray, ArenaString};
-use fxhash::hash;
-
-/// An arena backed hash table that maps keys to values.
-/// The hash table is backed by two arrays, one for the keys
-/// and one for the values. The keys are stored as strings
-/// and the values can be any type.
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub struct ArenaTable<V> {
-    arena: *const Arena,
-    keys: ArenaArray<Key>,
-    values: ArenaArray<V>,
+use fxhash::FxBuildHasher;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::BuildHasher;
+
+/// An arena backed hash table that maps string keys to values.
+/// This is an `ArenaMap` whose keys are copied into the arena as
+/// `ArenaString`s, so lookups and inserts take a plain `&str` and only
+/// new keys are copied. Iteration yields entries in insertion order,
+/// which is kept by `shift_remove` and `retain` and changed by `remove`,
+/// which moves the last entry into the gap, and the `sort_*` methods.
+/// Removing an entry frees its slot for later inserts, but the arena cannot
+/// take the bytes of its key back. They stay in the arena until it is reset,
+/// so a table that keeps replacing its keys keeps growing its arena.
+/// Keys are hashed with fxhash unless another `BuildHasher` is given.
+pub struct ArenaTable<'a, V, S = FxBuildHasher> {
+    map: ArenaMap<'a, ArenaString<'a>, V, S>,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Key {
-    key: ArenaString,
+/// A key stored in an `ArenaTable`, along with its hash.
+pub type Key<'a> = Bucket<ArenaString<'a>>;
+
+/// A view into a single entry of an `ArenaTable`, returned by
+/// `ArenaTable::entry`.
+pub enum Entry<'t, 'a, 'k, V, S = FxBuildHasher> {
+    Occupied(OccupiedEntry<'t, 'a, V, S>),
+    Vacant(VacantEntry<'t, 'a, 'k, V, S>),
+}
+
+/// An entry for a key that is in the table.
+pub struct OccupiedEntry<'t, 'a, V, S = FxBuildHasher> {
+    map: &'t mut ArenaMap<'a, ArenaString<'a>, V, S>,
+    index: usize,
+}
+
+/// An entry for a key that is not in the table yet. It remembers where in
+/// the index the key belongs, so inserting does not hash it again.
+pub struct VacantEntry<'t, 'a, 'k, V, S = FxBuildHasher> {
+    map: &'t mut ArenaMap<'a, ArenaString<'a>, V, S>,
+    key: &'k str,
     hash: usize,
+    slot: usize,
 }
 
-impl<V> ArenaTable<V> {
-    pub fn new(arena: &Arena, capacity: usize) -> Option<Self> {
-        let keys = arena.make_array(capacity)?;
-        let values = arena.make_array(capacity)?;
+impl<'a, V> ArenaTable<'a, V> {
+    pub fn new(arena: &'a Arena, capacity: usize) -> Option<Self> {
+        ArenaMap::new(arena, capacity).map(|map| Self { map })
+    }
+}
+
+impl<'a, V, S: BuildHasher> ArenaTable<'a, V, S> {
+    /// Creates a table that hashes its keys with `hash_builder` instead of
+    /// fxhash. Fxhash is fast but easy to attack, so tables keyed by
+    /// untrusted input should use a keyed hasher such as `RandomState`.
+    pub fn with_hasher(arena: &'a Arena, capacity: usize, hash_builder: S) -> Option<Self> {
+        ArenaMap::with_hasher(arena, capacity, hash_builder).map(|map| Self { map })
+    }
+
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    /// Makes the table grow instead of refusing new keys once it is full.
+    pub fn growable(self) -> Self {
+        Self {
+            map: self.map.growable(),
+        }
+    }
+
+    /// Sets the largest share of index slots that may point at entries, see
+    /// `ArenaMap::with_load_factor`.
+    pub fn with_load_factor(self, load_factor: f32) -> Self {
+        Self {
+            map: self.map.with_load_factor(load_factor),
+        }
+    }
 
-        Some(Self {
-            arena,
-            keys,
-            values,
-        })
+    pub fn is_growable(&self) -> bool {
+        self.map.is_growable()
+    }
+
+    pub fn load_factor(&self) -> f32 {
+        self.map.load_factor()
+    }
+
+    /// Returns the bytes of the arrays that growing has left behind in the
+    /// arena.
+    pub fn wasted(&self) -> usize {
+        self.map.wasted()
     }
 
     pub fn capacity(&self) -> usize {
-        self.keys.capacity()
+        self.map.capacity()
     }
 
     pub fn len(&self) -> usize {
-        self.keys.len()
+        self.map.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.keys.is_empty()
+        self.map.is_empty()
     }
 
     pub fn generation(&self) -> usize {
-        self.keys.generation()
+        self.map.generation()
     }
 
-    pub fn get_index(&self, key: &str) -> Option<usize> {
-        let hash = hash(key);
+    pub fn is_valid(&self) -> bool {
+        self.map.is_valid()
+    }
 
-        for (i, k) in self.keys.iter().enumerate() {
-            if hash == k.hash {
-                return Some(i);
-            }
-        }
+    pub fn try_get(&self, key: &str) -> Option<&V> {
+        self.map.try_get(key)
+    }
 
-        None
+    pub fn get_index(&self, key: &str) -> Option<usize> {
+        self.map.get_index(key)
     }
 
     pub fn get(&self, key: &str) -> Option<&V> {
-        match self.get_index(key) {
-            Some(i) => Some(&self.values[i]),
-            None => None,
-        }
+        self.map.get(key)
     }
 
     pub fn get_key_value(&self, key: &str) -> Option<(&str, &V)> {
-        self.get_index(key)
-            .map(|i| (&*self.keys[i].key, &self.values[i]))
+        self.map.get_key_value(key).map(|(k, v)| (k.as_ref(), v))
     }
 
     pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
-        match self.get_index(key) {
-            Some(i) => Some(&mut self.values[i]),
-            None => None,
+        self.map.get_mut(key)
+    }
+
+    /// Inserts `value` under `key`, copying the key into the arena if it is
+    /// not in the table yet. Returns the value it replaced, if any, or hands
+    /// `value` back if the key is new and the table or its arena is full.
+    pub fn insert(&mut self, key: &str, value: V) -> Result<Option<V>, V> {
+        let hash = self.map.hash(key);
+
+        match self.map.find(hash, key) {
+            Ok(i) => Ok(Some(core::mem::replace(self.map.value_mut(i), value))),
+            Err(slot) => match vacant_key(&mut self.map, hash, slot, key) {
+                Some((slot, key)) => {
+                    self.map.push_entry(slot, hash, key, value);
+                    Ok(None)
+                }
+                None => Err(value),
+            },
         }
     }
 
-    pub fn insert(&mut self, key: &str, value: V) -> bool {
-        if self.capacity() > self.len() {
-            let hash = hash(key);
-            let arena = unsafe { &*self.arena };
-            let string = arena.push_string(key).unwrap();
-            self.keys.push(Key { key: string, hash });
-            self.values.push(value);
-            return true;
+    /// Inserts `value` under `key` unless the key is already in the table.
+    /// On failure the value is handed back inside the error.
+    pub fn try_insert(&mut self, key: &str, value: V) -> Result<&mut V, InsertError<V>> {
+        let hash = self.map.hash(key);
+
+        match self.map.find(hash, key) {
+            Ok(_) => Err(InsertError::Occupied(value)),
+            Err(slot) => match vacant_key(&mut self.map, hash, slot, key) {
+                Some((slot, key)) => {
+                    let i = self.map.push_entry(slot, hash, key, value);
+                    Ok(self.map.value_mut(i))
+                }
+                None => Err(InsertError::Full(value)),
+            },
         }
+    }
 
-        false
+    /// Inserts `value` under a key that already lives in an arena, without
+    /// copying it. If the key is already in the table, its value is replaced
+    /// and returned, and the existing key is kept. The value is handed back
+    /// if the key is new and the table is full.
+    pub fn insert_string(&mut self, key: ArenaString<'a>, value: V) -> Result<Option<V>, V> {
+        self.map.insert(key, value)
+    }
+
+    /// Looks `key` up once, returning an entry that can be used to read,
+    /// update or insert its value without hashing the key again.
+    pub fn entry<'k>(&mut self, key: &'k str) -> Entry<'_, 'a, 'k, V, S> {
+        let hash = self.map.hash(key);
+
+        match self.map.find(hash, key) {
+            Ok(index) => Entry::Occupied(OccupiedEntry {
+                map: &mut self.map,
+                index,
+            }),
+            Err(slot) => Entry::Vacant(VacantEntry {
+                map: &mut self.map,
+                key,
+                hash,
+                slot,
+            }),
+        }
+    }
+
+    /// Removes `key` from the table and returns its value, moving the last
+    /// entry into its place. This is O(1) but changes the iteration order,
+    /// `shift_remove` keeps it.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    /// Like `remove`, but also hands back the key that was stored.
+    pub fn remove_entry(&mut self, key: &str) -> Option<(ArenaString<'a>, V)> {
+        self.map.remove_entry(key)
+    }
+
+    /// Removes `key` from the table and returns its value, keeping the
+    /// insertion order. This is O(capacity), see `ArenaMap::shift_remove`.
+    pub fn shift_remove(&mut self, key: &str) -> Option<V> {
+        self.map.shift_remove(key)
+    }
+
+    /// Like `shift_remove`, but also hands back the key that was stored.
+    pub fn shift_remove_entry(&mut self, key: &str) -> Option<(ArenaString<'a>, V)> {
+        self.map.shift_remove_entry(key)
+    }
+
+    /// The same as `remove`.
+    pub fn swap_remove(&mut self, key: &str) -> Option<V> {
+        self.map.swap_remove(key)
+    }
+
+    /// The same as `remove_entry`.
+    pub fn swap_remove_entry(&mut self, key: &str) -> Option<(ArenaString<'a>, V)> {
+        self.map.swap_remove_entry(key)
+    }
+
+    /// Keeps only the entries for which `f` returns true, in their order.
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &mut V) -> bool) {
+        self.map.retain(|key, value| f(key, value))
     }
 
     pub fn contains_key(&self, key: &str) -> bool {
-        self.get_index(key).is_some()
+        self.map.contains_key(key)
+    }
+
+    /// Sorts the entries by key. The sort is stable.
+    pub fn sort_keys(&mut self) {
+        self.map.sort_keys();
+    }
+
+    /// Sorts the entries with `cmp`, keeping the order of equal entries.
+    pub fn sort_by(&mut self, mut cmp: impl FnMut(&str, &V, &str, &V) -> Ordering) {
+        self.map.sort_by(|k1, v1, k2, v2| cmp(k1, v1, k2, v2));
+    }
+
+    /// Sorts the entries by the key `f` extracts from them. The sort is
+    /// unstable, so equal entries may end up in any order.
+    pub fn sort_unstable_by_key<T: Ord>(&mut self, mut f: impl FnMut(&str, &V) -> T) {
+        self.map.sort_unstable_by_key(|key, value| f(key, value));
     }
 
-    pub fn keys(&self) -> &ArenaArray<Key> {
-        &self.keys
+    pub fn keys(&self) -> &ArenaArray<'a, Key<'a>> {
+        self.map.buckets()
     }
 
-    pub fn values(&self) -> &ArenaArray<V> {
-        &self.values
+    pub fn values(&self) -> &ArenaArray<'a, V> {
+        self.map.value_array()
     }
 
     pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
-        self.values.iter_mut()
+        self.map.value_slices_mut().1.iter_mut()
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
-        self.keys
-            .iter()
-            .zip(self.values.iter())
-            .map(|(k, v)| (&*k.key, v))
+        let keys = self.map.buckets().as_ref();
+        let values = self.map.value_array().as_ref();
+
+        keys.iter().zip(values).map(|(k, v)| (k.key().as_ref(), v))
     }
 
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut V)> {
-        self.keys
-            .iter()
-            .zip(self.values.iter_mut())
-            .map(|(k, v)| (&*k.key, v))
+        let (keys, values) = self.map.value_slices_mut();
+
+        keys.iter().zip(values).map(|(k, v)| (k.key().as_ref(), v))
     }
 
     pub fn clear(&mut self) {
-        self.keys.clear();
-        self.values.clear();
+        self.map.clear();
+    }
+}
+
+impl<'t, 'a, 'k, V, S: BuildHasher> Entry<'t, 'a, 'k, V, S> {
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the table or its arena is full, see
+    /// `or_try_insert` for a fallible alternative.
+    pub fn or_insert(self, default: V) -> &'t mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns
+    /// the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the table or its arena is full, see
+    /// `or_try_insert_with` for a fallible alternative.
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'t mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Like `or_insert`, but hands `default` back if the entry is vacant
+    /// and the table or its arena is full.
+    pub fn or_try_insert(self, default: V) -> Result<&'t mut V, InsertError<V>> {
+        match self {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => entry.try_insert(default),
+        }
+    }
+
+    /// Like `or_insert_with`, but hands the value back if the entry is
+    /// vacant and the table or its arena is full.
+    pub fn or_try_insert_with(
+        self,
+        default: impl FnOnce() -> V,
+    ) -> Result<&'t mut V, InsertError<V>> {
+        match self {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => entry.try_insert(default()),
+        }
+    }
+
+    /// Calls `f` with the value if the entry is occupied.
+    pub fn and_modify(mut self, f: impl FnOnce(&mut V)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+
+        self
+    }
+}
+
+impl<'t, V: Default, S: BuildHasher> Entry<'t, '_, '_, V, S> {
+    /// # Panics
+    ///
+    /// Panics if the entry is vacant and the table or its arena is full, see
+    /// `or_try_insert_with` for a fallible alternative.
+    pub fn or_default(self) -> &'t mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'t, 'a, V, S: BuildHasher> OccupiedEntry<'t, 'a, V, S> {
+    pub fn key(&self) -> &str {
+        self.map.buckets()[self.index].key()
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map.value_array()[self.index]
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.value_mut(self.index)
+    }
+
+    pub fn into_mut(self) -> &'t mut V {
+        self.map.value_mut(self.index)
+    }
+
+    /// Replaces the value, returning the old one.
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the entry like `ArenaTable::remove` and returns its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (ArenaString<'a>, V) {
+        self.map.swap_remove_index(self.index)
+    }
+
+    /// Removes the entry like `ArenaTable::shift_remove` and returns its
+    /// value.
+    pub fn shift_remove(self) -> V {
+        self.shift_remove_entry().1
+    }
+
+    pub fn shift_remove_entry(self) -> (ArenaString<'a>, V) {
+        self.map.shift_remove_index(self.index)
+    }
+}
+
+impl<'t, V, S: BuildHasher> VacantEntry<'t, '_, '_, V, S> {
+    pub fn key(&self) -> &str {
+        self.key
+    }
+
+    /// Copies the key into the arena and inserts `value` under it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table or its arena is full, see `try_insert` for a
+    /// fallible alternative.
+    pub fn insert(self, value: V) -> &'t mut V {
+        match self.try_insert(value) {
+            Ok(value) => value,
+            Err(_) => panic!("ArenaTable is full"),
+        }
+    }
+
+    /// Copies the key into the arena and inserts `value` under it. If the
+    /// table or its arena is full, the value is handed back inside an
+    /// `InsertError::Full`.
+    pub fn try_insert(self, value: V) -> Result<&'t mut V, InsertError<V>> {
+        let Some((slot, key)) = vacant_key(self.map, self.hash, self.slot, self.key) else {
+            return Err(InsertError::Full(value));
+        };
+
+        let index = self.map.push_entry(slot, self.hash, key, value);
+
+        Ok(self.map.value_mut(index))
+    }
+}
+
+/// Makes room in `map` for a new entry and copies `key` into the arena for
+/// it. Returns the slot to insert at and the copied key.
+fn vacant_key<'a, V, S: BuildHasher>(
+    map: &mut ArenaMap<'a, ArenaString<'a>, V, S>,
+    hash: usize,
+    slot: usize,
+    key: &str,
+) -> Option<(usize, ArenaString<'a>)> {
+    let slot = map.vacant_slot(hash, slot)?;
+    let key = map.arena().push_string(key)?;
+
+    Some((slot, key))
+}
+
+impl<V: PartialEq, S: BuildHasher> PartialEq for ArenaTable<'_, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<V: Eq, S: BuildHasher> Eq for ArenaTable<'_, V, S> {}
+
+impl<V: PartialOrd, S: BuildHasher> PartialOrd for ArenaTable<'_, V, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<V: Ord, S: BuildHasher> Ord for ArenaTable<'_, V, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<V: Debug, S: BuildHasher> Debug for ArenaTable<'_, V, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ArenaTable;
+    use super::{ArenaTable, Entry, InsertError};
     use crate::Arena;
+    use std::collections::hash_map::{DefaultHasher, RandomState};
+    use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
 
     #[test]
     fn test_table_get() {
@@ -140,15 +491,15 @@ mod tests {
         assert_eq!(table.capacity(), 10);
         assert_eq!(table.len(), 0);
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
-        assert_eq!(table.insert(&"baz", 44), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+        assert_eq!(table.insert("baz", 44), Ok(None));
 
         assert_eq!(table.len(), 3);
 
-        assert_eq!(table.get(&"foo"), Some(&42));
-        assert_eq!(table.get(&"bar"), Some(&43));
-        assert_eq!(table.get(&"baz"), Some(&44));
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&43));
+        assert_eq!(table.get("baz"), Some(&44));
     }
 
     #[test]
@@ -157,18 +508,18 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
-        let foo = table.get_mut(&"foo").unwrap();
+        let foo = table.get_mut("foo").unwrap();
         *foo = 100;
 
-        let bar = table.get_mut(&"bar").unwrap();
+        let bar = table.get_mut("bar").unwrap();
         *bar = 200;
 
-        assert_eq!(table.get(&"foo"), Some(&100));
-        assert_eq!(table.get(&"bar"), Some(&200));
-        assert_eq!(table.get(&"baz"), None);
+        assert_eq!(table.get("foo"), Some(&100));
+        assert_eq!(table.get("bar"), Some(&200));
+        assert_eq!(table.get("baz"), None);
     }
 
     #[test]
@@ -177,18 +528,18 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
-        let (key, value) = table.get_key_value(&"foo").unwrap();
+        let (key, value) = table.get_key_value("foo").unwrap();
         assert_eq!(key, "foo");
         assert_eq!(value, &42);
 
-        let (key, value) = table.get_key_value(&"bar").unwrap();
+        let (key, value) = table.get_key_value("bar").unwrap();
         assert_eq!(key, "bar");
         assert_eq!(value, &43);
 
-        let pair = table.get_key_value(&"baz");
+        let pair = table.get_key_value("baz");
         assert_eq!(pair, None);
     }
 
@@ -200,16 +551,16 @@ mod tests {
 
         assert_eq!(table.capacity(), 2);
         assert_eq!(table.len(), 0);
-        assert_eq!(table.is_empty(), true);
+        assert!(table.is_empty());
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
-        assert_eq!(table.insert(&"baz", 44), false);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+        assert_eq!(table.try_insert("baz", 44), Err(InsertError::Full(44)));
 
         assert_eq!(table.len(), 2);
-        assert_eq!(table.get(&"foo"), Some(&42));
-        assert_eq!(table.get(&"bar"), Some(&43));
-        assert_eq!(table.get(&"baz"), None);
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&43));
+        assert_eq!(table.get("baz"), None);
     }
 
     #[test]
@@ -218,14 +569,89 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
-        assert_eq!(table.insert(&"baz", 44), false);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+        assert_eq!(table.try_insert("baz", 44), Err(InsertError::Full(44)));
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&43));
+        assert_eq!(table.get("baz"), None);
+    }
+
+    #[test]
+    fn test_table_insert_replaces() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        assert_eq!(table.insert("foo", 42), Ok(None));
+
+        let occupied = arena.occupied();
+
+        // Replacing a value does not copy the key again.
+        assert_eq!(table.insert("foo", 43), Ok(Some(42)));
+        assert_eq!(table.insert("foo", 44), Ok(Some(43)));
+        assert_eq!(arena.occupied(), occupied);
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.get("foo"), Some(&44));
+
+        // Replacing works even when the table is full.
+        assert_eq!(table.insert("bar", 45), Ok(None));
+        assert_eq!(table.insert("bar", 46), Ok(Some(45)));
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![("foo", &44), ("bar", &46)]);
+    }
+
+    #[test]
+    fn test_table_insert_full() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 1).unwrap();
+
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Err(43));
+        assert_eq!(table.insert("foo", 44), Ok(Some(42)));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_table_try_insert() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        assert_eq!(table.try_insert("foo", 42), Ok(&mut 42));
+        assert_eq!(table.try_insert("foo", 43), Err(InsertError::Occupied(43)));
 
+        *table.try_insert("bar", 44).unwrap() += 1;
+
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&45));
         assert_eq!(table.len(), 2);
-        assert_eq!(table.get(&"foo"), Some(&42));
-        assert_eq!(table.get(&"bar"), Some(&43));
-        assert_eq!(table.get(&"baz"), None);
+    }
+
+    #[test]
+    fn test_table_insert_string() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+        let key = arena.push_string("foo").unwrap();
+        let ptr = key.as_ptr();
+        let occupied = arena.occupied();
+
+        assert_eq!(table.insert_string(key, 42), Ok(None));
+        assert_eq!(arena.occupied(), occupied);
+
+        let (key, value) = table.get_key_value("foo").unwrap();
+
+        assert_eq!(key.as_ptr(), ptr);
+        assert_eq!(value, &42);
+
+        let other = arena.push_string("foo").unwrap();
+
+        assert_eq!(table.insert_string(other, 43), Ok(Some(42)));
+        assert_eq!(table.get_key_value("foo").unwrap().0.as_ptr(), ptr);
     }
 
     #[test]
@@ -234,14 +660,14 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
         let keys = table.keys();
 
         assert_eq!(keys.len(), 2);
-        assert_eq!(&*keys[0].key, "foo");
-        assert_eq!(&*keys[1].key, "bar");
+        assert_eq!(keys[0].key().as_ref(), "foo");
+        assert_eq!(keys[1].key().as_ref(), "bar");
     }
 
     #[test]
@@ -250,8 +676,8 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
         let values: Vec<&i32> = table.values().iter().collect();
         assert_eq!(values, vec![&42, &43]);
@@ -263,8 +689,8 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
         for value in table.values_mut() {
             *value += 1;
@@ -280,8 +706,8 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
         let items: Vec<(&str, &i32)> = table.iter().collect();
         assert_eq!(items, vec![("foo", &42), ("bar", &43)]);
@@ -293,8 +719,8 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
         for (_, value) in table.iter_mut() {
             *value += 1;
@@ -310,15 +736,52 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
 
         assert_eq!(table.len(), 2);
 
         table.clear();
 
         assert_eq!(table.len(), 0);
-        assert_eq!(table.is_empty(), true);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_table_many_entries() {
+        let arena = Arena::new(1024 * 1024);
+        let keys: Vec<String> = (0..5000).map(|i| format!("key{}", i)).collect();
+
+        let mut table = ArenaTable::<usize>::new(&arena, keys.len()).unwrap();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(table.insert(key, i), Ok(None));
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(table.get(key), Some(&i));
+            assert_eq!(table.get_index(key), Some(i));
+        }
+
+        assert!(!table.contains_key("key5000"));
+        assert!(table.iter().map(|(_, value)| *value).eq(0..5000));
+    }
+
+    #[test]
+    fn test_table_clear_reuse() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+
+        table.clear();
+
+        assert_eq!(table.get("foo"), None);
+        assert_eq!(table.insert("baz", 44), Ok(None));
+        assert_eq!(table.get("baz"), Some(&44));
+        assert_eq!(table.iter().count(), 1);
     }
 
     #[test]
@@ -327,11 +790,515 @@ mod tests {
 
         let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
 
-        assert_eq!(table.insert(&"foo", 42), true);
-        assert_eq!(table.insert(&"bar", 43), true);
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+
+        assert!(table.contains_key("foo"));
+        assert!(table.contains_key("bar"));
+        assert!(!table.contains_key("baz"));
+    }
+
+    #[test]
+    fn test_table_is_valid() {
+        let arena = Arena::new(1024);
+        let checkpoint = arena.checkpoint();
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        assert_eq!(table.insert("foo", 42), Ok(None));
+
+        assert!(table.is_valid());
+        assert_eq!(table.try_get("foo"), Some(&42));
+
+        unsafe { arena.rewind_unchecked(checkpoint) };
+
+        assert!(!table.is_valid());
+        assert_eq!(table.try_get("foo"), None);
+    }
+
+    /// A hasher that sends every key to the same slot.
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    fn collide() -> BuildHasherDefault<Collide> {
+        BuildHasherDefault::default()
+    }
+
+    #[test]
+    fn test_table_forced_collisions() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32, _>::with_hasher(&arena, 8, collide()).unwrap();
+
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+        assert_eq!(table.insert("baz", 44), Ok(None));
+
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&43));
+        assert_eq!(table.get("baz"), Some(&44));
+        assert_eq!(table.get("qux"), None);
+
+        assert_eq!(table.get_key_value("bar"), Some(("bar", &43)));
+        assert_eq!(table.get_key_value("qux"), None);
+
+        assert!(table.contains_key("baz"));
+        assert!(!table.contains_key("qux"));
+        assert!(!table.contains_key(""));
+    }
+
+    #[test]
+    fn test_table_forced_collisions_get_mut() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32, _>::with_hasher(&arena, 4, collide()).unwrap();
+
+        assert_eq!(table.insert("foo", 42), Ok(None));
+        assert_eq!(table.insert("bar", 43), Ok(None));
+
+        *table.get_mut("bar").unwrap() += 100;
+
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&143));
+        assert_eq!(table.get_mut("baz"), None);
+    }
+
+    #[test]
+    fn test_table_forced_collisions_full_index() {
+        let arena = Arena::new(4096);
+        let keys = ["a", "b", "c", "d", "e", "f", "g"];
+
+        let mut table = ArenaTable::<usize, _>::with_hasher(&arena, keys.len(), collide()).unwrap();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(table.insert(key, i), Ok(None));
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(table.get(key), Some(&i));
+        }
+
+        assert!(!table.contains_key("h"));
+    }
+
+    #[test]
+    fn test_table_fxhash_collision() {
+        let arena = Arena::new(1024);
+
+        // These two keys really do share an fxhash.
+        assert_eq!(fxhash::hash("symbol_19"), fxhash::hash("symbol_94"));
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        assert_eq!(table.insert("symbol_19", 19), Ok(None));
+
+        assert_eq!(table.get("symbol_94"), None);
+        assert_eq!(table.insert("symbol_94", 94), Ok(None));
+        assert_eq!(table.get("symbol_19"), Some(&19));
+        assert_eq!(table.get("symbol_94"), Some(&94));
+    }
+
+    #[test]
+    fn test_table_entry() {
+        let arena = Arena::new(4096);
+        let words = ["a", "b", "a", "c", "b", "a"];
+
+        let mut table = ArenaTable::<i32>::new(&arena, 4).unwrap();
+
+        for word in words {
+            *table.entry(word).or_insert(0) += 1;
+        }
+
+        let counts: Vec<(&str, &i32)> = table.iter().collect();
+        assert_eq!(counts, vec![("a", &3), ("b", &2), ("c", &1)]);
+    }
+
+    #[test]
+    fn test_table_entry_variants() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 4).unwrap();
+
+        table.insert("foo", 42).unwrap();
+
+        match table.entry("foo") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), "foo");
+                assert_eq!(entry.get(), &42);
+                assert_eq!(entry.insert(43), 42);
+            }
+            Entry::Vacant(_) => panic!("foo should be occupied"),
+        }
+
+        match table.entry("bar") {
+            Entry::Occupied(_) => panic!("bar should be vacant"),
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.key(), "bar");
+                assert_eq!(entry.insert(44), &mut 44);
+            }
+        }
+
+        assert_eq!(table.get("foo"), Some(&43));
+        assert_eq!(table.get("bar"), Some(&44));
+    }
+
+    #[test]
+    fn test_table_entry_and_modify() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 4).unwrap();
+
+        table.entry("foo").and_modify(|value| *value += 1).or_insert(42);
+        table.entry("foo").and_modify(|value| *value += 1).or_insert(42);
+
+        assert_eq!(table.entry("bar").or_insert_with(|| 7), &mut 7);
+        assert_eq!(table.entry("bar").or_insert_with(|| 8), &mut 7);
+        assert_eq!(table.entry("baz").or_default(), &mut 0);
+
+        assert_eq!(table.get("foo"), Some(&43));
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn test_table_entry_full() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 1).unwrap();
+
+        assert_eq!(table.entry("foo").or_try_insert(42), Ok(&mut 42));
+        assert_eq!(table.entry("foo").or_try_insert(43), Ok(&mut 42));
+        assert_eq!(table.entry("bar").or_try_insert(44), Err(InsertError::Full(44)));
+        assert_eq!(table.entry("bar").or_try_insert_with(|| 45), Err(InsertError::Full(45)));
+
+        match table.entry("bar") {
+            Entry::Occupied(_) => panic!("bar should be vacant"),
+            Entry::Vacant(entry) => {
+                assert_eq!(entry.try_insert(46), Err(InsertError::Full(46)));
+            }
+        }
+
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ArenaTable is full")]
+    fn test_table_entry_or_default_full() {
+        let arena = Arena::new(1024);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 1).unwrap();
+
+        table.entry("foo").or_default();
+        table.entry("bar").or_default();
+    }
+
+    #[test]
+    fn test_table_entry_collisions() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32, _>::with_hasher(&arena, 4, collide()).unwrap();
+
+        *table.entry("foo").or_default() += 1;
+        *table.entry("bar").or_default() += 2;
+        *table.entry("foo").or_default() += 3;
+
+        assert_eq!(table.get("foo"), Some(&4));
+        assert_eq!(table.get("bar"), Some(&2));
+    }
+
+    #[test]
+    fn test_table_remove() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 4).unwrap();
+
+        table.insert("foo", 42).unwrap();
+        table.insert("bar", 43).unwrap();
+        table.insert("baz", 44).unwrap();
+
+        assert_eq!(table.remove("bar"), Some(43));
+        assert_eq!(table.remove("bar"), None);
+        assert_eq!(table.remove("qux"), None);
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get("bar"), None);
+        assert_eq!(table.get("baz"), Some(&44));
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![("foo", &42), ("baz", &44)]);
+
+        let (key, value) = table.remove_entry("foo").unwrap();
+
+        assert_eq!(key.as_ref(), "foo");
+        assert_eq!(value, 42);
+        assert_eq!(table.get_index("baz"), Some(0));
+    }
+
+    #[test]
+    fn test_table_swap_remove() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 4).unwrap();
+
+        table.insert("foo", 42).unwrap();
+        table.insert("bar", 43).unwrap();
+        table.insert("baz", 44).unwrap();
+
+        assert_eq!(table.swap_remove("foo"), Some(42));
+        assert_eq!(table.swap_remove("foo"), None);
+
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![("baz", &44), ("bar", &43)]);
+        assert_eq!(table.get("baz"), Some(&44));
+        assert_eq!(table.get("bar"), Some(&43));
+
+        let (key, value) = table.swap_remove_entry("bar").unwrap();
+
+        assert_eq!((key.as_ref(), value), ("bar", 43));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_table_retain() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 8).unwrap();
+
+        for (i, key) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+            table.insert(key, i as i32).unwrap();
+        }
+
+        table.retain(|_, value| {
+            *value *= 10;
+            *value % 20 == 0
+        });
+
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![("a", &0), ("c", &20), ("e", &40)]);
+        assert_eq!(table.get("b"), None);
+        assert_eq!(table.get("e"), Some(&40));
+    }
+
+    #[test]
+    fn test_table_order_survives_removal() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 8).unwrap();
+
+        for (i, key) in ["e", "b", "d", "a", "c"].iter().enumerate() {
+            table.insert(key, i as i32).unwrap();
+        }
+
+        table.shift_remove("d");
+        table.insert("f", 5).unwrap();
+        table.insert("b", 6).unwrap();
+
+        let keys: Vec<_> = table.iter().map(|(key, _)| key).collect();
+
+        assert_eq!(keys, vec!["e", "b", "a", "c", "f"]);
+    }
+
+    #[test]
+    fn test_table_sort() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 8).unwrap();
+
+        for (i, key) in ["e", "b", "d", "a", "c", "f"].iter().enumerate() {
+            table.insert(key, i as i32 % 2).unwrap();
+        }
+
+        table.remove("d");
+        table.sort_keys();
+
+        assert_eq!(
+            table.iter().collect::<Vec<_>>(),
+            vec![("a", &1), ("b", &1), ("c", &0), ("e", &0), ("f", &1)]
+        );
+
+        // Stable, so equal values stay sorted by key.
+        table.sort_by(|_, v1, _, v2| v1.cmp(v2));
+
+        let keys: Vec<_> = table.iter().map(|(key, _)| key).collect();
+
+        assert_eq!(keys, vec!["c", "e", "a", "b", "f"]);
+
+        table.sort_unstable_by_key(|key, _| std::cmp::Reverse(key.to_string()));
+
+        let keys: Vec<_> = table.iter().map(|(key, _)| key).collect();
+
+        assert_eq!(keys, vec!["f", "e", "c", "b", "a"]);
+        assert!(["a", "b", "c", "e", "f"].iter().all(|key| table.get(key).is_some()));
+        assert_eq!(table.get("d"), None);
+        assert_eq!(table.get_index("b"), Some(3));
+    }
+
+    #[test]
+    fn test_table_remove_reuses_room() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        table.insert("foo", 42).unwrap();
+        table.insert("bar", 43).unwrap();
+
+        assert_eq!(table.try_insert("baz", 44), Err(InsertError::Full(44)));
+        assert_eq!(table.remove("foo"), Some(42));
+        assert_eq!(table.try_insert("baz", 44), Ok(&mut 44));
+
+        assert_eq!(table.capacity(), 2);
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![("bar", &43), ("baz", &44)]);
+    }
+
+    #[test]
+    fn test_table_remove_keeps_key_bytes() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 2).unwrap();
+
+        table.insert("foo", 42).unwrap();
+
+        let occupied = arena.occupied();
+
+        // The entry's slot is reused, but the key is copied in again.
+        assert_eq!(table.remove("foo"), Some(42));
+        assert_eq!(table.insert("foo", 43), Ok(None));
+        assert_eq!(arena.occupied(), occupied + 3);
+    }
+
+    #[test]
+    fn test_table_remove_collisions() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32, _>::with_hasher(&arena, 4, collide()).unwrap();
+
+        table.insert("foo", 42).unwrap();
+        table.insert("bar", 43).unwrap();
+        table.insert("baz", 44).unwrap();
+
+        assert_eq!(table.remove("foo"), Some(42));
+        assert_eq!(table.get("bar"), Some(&43));
+        assert_eq!(table.get("baz"), Some(&44));
+
+        assert_eq!(table.swap_remove("bar"), Some(43));
+        assert_eq!(table.get("baz"), Some(&44));
+
+        table.insert("qux", 45).unwrap();
+
+        assert_eq!(table.get("baz"), Some(&44));
+        assert_eq!(table.get("qux"), Some(&45));
+    }
+
+    #[test]
+    fn test_table_remove_churn() {
+        let arena = Arena::new(1024 * 64);
+        let keys: Vec<String> = (0..16).map(|i| format!("key{}", i)).collect();
+
+        let mut table = ArenaTable::<usize>::new(&arena, 8).unwrap();
+
+        for round in 0..100 {
+            for key in keys.iter().skip(round % 8).take(8) {
+                table.insert(key, round).unwrap();
+            }
+
+            for key in keys.iter().skip(round % 8).take(8).step_by(2) {
+                assert_eq!(table.remove(key), Some(round));
+            }
+
+            for key in keys.iter().skip(round % 8 + 1).take(8).step_by(2) {
+                assert_eq!(table.swap_remove(key), Some(round));
+            }
+
+            assert!(table.is_empty());
+            assert!(keys.iter().all(|key| !table.contains_key(key)));
+        }
+    }
+
+    #[test]
+    fn test_table_entry_remove() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 4).unwrap();
+
+        table.insert("foo", 42).unwrap();
+        table.insert("bar", 43).unwrap();
+
+        if let Entry::Occupied(entry) = table.entry("foo") {
+            assert_eq!(entry.remove(), 42);
+        }
+
+        assert_eq!(table.get("foo"), None);
+        assert_eq!(table.get("bar"), Some(&43));
+    }
+
+    #[test]
+    fn test_table_with_hasher() {
+        let arena = Arena::new(4096);
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+
+        let mut table = ArenaTable::with_hasher(&arena, 4, hasher).unwrap();
+
+        table.insert("foo", 42).unwrap();
+        table.insert("bar", 43).unwrap();
+
+        let expected = table.hasher().hash_one("foo") as usize;
+
+        assert_ne!(expected, fxhash::hash("foo"));
+        assert_eq!(table.keys()[0].hash(), expected);
+        assert_eq!(table.get("foo"), Some(&42));
+        assert_eq!(table.get("bar"), Some(&43));
+    }
+
+    #[test]
+    fn test_table_random_state() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::with_hasher(&arena, 4, RandomState::new()).unwrap();
+
+        *table.entry("symbol_19").or_default() += 19;
+        *table.entry("symbol_94").or_default() += 94;
+
+        assert_eq!(table.get("symbol_19"), Some(&19));
+        assert_eq!(table.get("symbol_94"), Some(&94));
+        assert_eq!(table.remove("symbol_19"), Some(19));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn test_table_growable() {
+        let arena = Arena::new(64 * 1024);
+        let keys: Vec<String> = (0..100).map(|i| format!("key{}", i)).collect();
+
+        let mut table = ArenaTable::<usize>::new(&arena, 1).unwrap().growable();
+
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(table.insert(key, i), Ok(None));
+        }
+
+        assert_eq!(table.len(), 100);
+        assert!(table.capacity() >= 100);
+        assert!(table.wasted() > 0);
+        assert!(table.iter().map(|(key, _)| key).eq(keys.iter().map(|key| key.as_str())));
+        assert!(keys.iter().enumerate().all(|(i, key)| table.get(key) == Some(&i)));
+    }
+
+    #[test]
+    fn test_table_growable_entry() {
+        let arena = Arena::new(4096);
+
+        let mut table = ArenaTable::<i32>::new(&arena, 1)
+            .unwrap()
+            .growable()
+            .with_load_factor(0.5);
+
+        for word in ["a", "b", "a", "c", "b", "a"] {
+            *table.entry(word).or_default() += 1;
+        }
 
-        assert_eq!(table.contains_key(&"foo"), true);
-        assert_eq!(table.contains_key(&"bar"), true);
-        assert_eq!(table.contains_key(&"baz"), false);
+        assert_eq!(table.load_factor(), 0.5);
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec![("a", &3), ("b", &2), ("c", &1)]);
     }
 }
@@ -0,0 +1,401 @@
+use super::{Arena, ArenaArray};
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+
+/// A growable array that is allocated in an arena. Regions it moves out of
+/// are only reclaimed when the arena is reset.
+pub struct ArenaVec<'a, T> {
+    array: ArenaArray<'a, T>,
+}
+
+/// Makes room for `additional` more elements than `len`, in place if possible
+/// and by relocating otherwise.
+pub(crate) fn grow<C>(
+    container: &mut C,
+    len: usize,
+    capacity: usize,
+    additional: usize,
+    grow_in_place: fn(&mut C, usize) -> bool,
+    relocate: fn(&mut C, usize) -> bool,
+) -> Option<()> {
+    let needed = len.checked_add(additional)?;
+
+    if needed <= capacity {
+        return Some(());
+    }
+
+    // Prefer doubling, so pushing one at a time stays amortized O(1),
+    // but settle for the exact amount when the arena is nearly full.
+    let doubled = capacity.saturating_mul(2).max(needed).max(4);
+    let capacities = [doubled, needed];
+
+    let grown = capacities.iter().any(|&capacity| grow_in_place(container, capacity))
+        || capacities.iter().any(|&capacity| relocate(container, capacity));
+
+    grown.then_some(())
+}
+
+impl<'a, T> ArenaVec<'a, T> {
+    pub fn new(arena: &'a Arena) -> Self {
+        Self {
+            array: ArenaArray::with_capacity(arena, 0).unwrap(),
+        }
+    }
+
+    pub fn with_capacity(arena: &'a Arena, capacity: usize) -> Option<Self> {
+        ArenaArray::with_capacity(arena, capacity).map(|array| Self { array })
+    }
+
+    pub fn len(&self) -> usize {
+        self.array.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.array.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.array.is_empty()
+    }
+
+    pub fn generation(&self) -> usize {
+        self.array.generation()
+    }
+
+    pub fn arena(&self) -> &'a Arena {
+        self.array.arena()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.array.is_valid()
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        self.array.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.array.as_mut_ptr()
+    }
+
+    pub fn reserve(&mut self, additional: usize) -> Option<()> {
+        let (len, capacity) = (self.len(), self.capacity());
+
+        grow(self, len, capacity, additional, Self::grow_in_place, Self::relocate)
+    }
+
+    fn grow_in_place(&mut self, capacity: usize) -> bool {
+        self.array.resize_in_place(capacity)
+    }
+
+    fn relocate(&mut self, capacity: usize) -> bool {
+        let Some(mut array) = ArenaArray::with_capacity(self.arena(), capacity) else {
+            return false;
+        };
+
+        let len = self.len();
+
+        // The old array must not drop the elements it no longer owns.
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.array.as_ptr(), array.as_mut_ptr(), len);
+            self.array.set_len(0);
+            array.set_len(len);
+        }
+
+        self.array = array;
+
+        true
+    }
+
+    /// Only gives capacity back if the vector is the most recent allocation.
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.len();
+
+        self.array.resize_in_place(len);
+    }
+
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        if self.reserve(1).is_none() {
+            return Err(value);
+        }
+
+        self.array.push(value);
+
+        Ok(())
+    }
+
+    /// Panics if the arena has no room left, see `try_push`.
+    pub fn push(&mut self, value: T) {
+        if self.try_push(value).is_err() {
+            panic!("ArenaVec could not grow");
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.array.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.array.clear();
+    }
+}
+
+impl<T: Clone> ArenaVec<'_, T> {
+    pub fn extend_from_slice(&mut self, values: &[T]) -> Option<()> {
+        self.reserve(values.len())?;
+
+        for value in values {
+            self.array.push(value.clone());
+        }
+
+        Some(())
+    }
+}
+
+impl<T> Extend<T> for ArenaVec<'_, T> {
+    /// Panics if the arena has no room left for the values.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+
+        // The lower bound may be too low but never too high, growing for it
+        // up front saves relocating several times.
+        let _ = self.reserve(iter.size_hint().0);
+
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T> Deref for ArenaVec<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.array.as_ref()
+    }
+}
+
+impl<T> DerefMut for ArenaVec<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.array.as_mut()
+    }
+}
+
+impl<T> AsRef<[T]> for ArenaVec<'_, T> {
+    fn as_ref(&self) -> &[T] {
+        self.array.as_ref()
+    }
+}
+
+impl<T> AsMut<[T]> for ArenaVec<'_, T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self.array.as_mut()
+    }
+}
+
+impl<T: Clone> Clone for ArenaVec<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            array: self.array.clone(),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArenaVec<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<T: Eq> Eq for ArenaVec<'_, T> {}
+
+impl<T: PartialOrd> PartialOrd for ArenaVec<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_ref().partial_cmp(other.as_ref())
+    }
+}
+
+impl<T: Ord> Ord for ArenaVec<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_ref().cmp(other.as_ref())
+    }
+}
+
+impl<T: Debug> Debug for ArenaVec<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.as_ref()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArenaVec;
+    use crate::Arena;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_vec_grows_in_place() {
+        let arena = Arena::new(1024);
+
+        let mut vec = ArenaVec::with_capacity(&arena, 2).unwrap();
+        let ptr = vec.as_ptr();
+
+        for i in 0..10u32 {
+            vec.push(i);
+        }
+
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec.capacity(), 16);
+        assert_eq!(arena.occupied(), 16 * 4);
+        assert!(vec.iter().copied().eq(0..10));
+    }
+
+    #[test]
+    fn test_vec_relocates() {
+        let arena = Arena::new(1024);
+
+        let mut vec = ArenaVec::with_capacity(&arena, 2).unwrap();
+
+        vec.push(1u32);
+        vec.push(2);
+
+        let ptr = vec.as_ptr();
+        let other = arena.push(3u32).unwrap();
+
+        vec.push(4);
+
+        assert_ne!(vec.as_ptr(), ptr);
+        assert_eq!(vec.as_ref(), &[1, 2, 4]);
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(*other, 3);
+        assert_eq!(arena.occupied(), 8 + 4 + 16);
+    }
+
+    #[test]
+    fn test_vec_grows_after_checkpoint() {
+        let mut arena = Arena::new(1024);
+        let outer = arena.checkpoint();
+
+        {
+            let mut vec = ArenaVec::new(&arena);
+
+            // Only taken so the vector grows past it, rewinding the outer
+            // checkpoint closes it as well.
+            let _inner = arena.checkpoint();
+
+            vec.extend(0..10u32);
+
+            assert!(vec.iter().copied().eq(0..10));
+        }
+
+        arena.rewind(outer);
+
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_vec_try_push() {
+        let arena = Arena::new(16);
+
+        let mut vec = ArenaVec::new(&arena);
+
+        for i in 0..4u32 {
+            assert_eq!(vec.try_push(i), Ok(()));
+        }
+
+        assert_eq!(vec.try_push(4), Err(4));
+        assert_eq!(vec.as_ref(), &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ArenaVec could not grow")]
+    fn test_vec_push_full() {
+        let arena = Arena::new(16);
+
+        let mut vec = ArenaVec::new(&arena);
+        vec.extend(0..5u32);
+    }
+
+    #[test]
+    fn test_vec_reserve_exact_fallback() {
+        let arena = Arena::new(40);
+
+        let mut vec = ArenaVec::with_capacity(&arena, 6).unwrap();
+
+        vec.extend(0..6u32);
+
+        // Doubling would need 48 bytes, but 10 elements still fit.
+        assert_eq!(vec.reserve(4), Some(()));
+        assert_eq!(vec.capacity(), 10);
+        assert_eq!(vec.reserve(5), None);
+        assert_eq!(vec.capacity(), 10);
+    }
+
+    #[test]
+    fn test_vec_shrink_to_fit() {
+        let arena = Arena::new(1024);
+
+        let mut vec = ArenaVec::with_capacity(&arena, 16).unwrap();
+
+        vec.extend_from_slice(&[1u64, 2, 3]).unwrap();
+        vec.shrink_to_fit();
+
+        assert_eq!(vec.capacity(), 3);
+        assert_eq!(arena.occupied(), 24);
+
+        arena.push(0u8).unwrap();
+        vec.shrink_to_fit();
+        vec.pop();
+        vec.shrink_to_fit();
+
+        assert_eq!(vec.capacity(), 3);
+    }
+
+    #[test]
+    fn test_vec_pop_and_drops() {
+        let value = Rc::new(42);
+        let arena = Arena::new(1024);
+
+        {
+            let mut vec = ArenaVec::new(&arena);
+
+            vec.extend((0..6).map(|_| value.clone()));
+            arena.push(0u8).unwrap();
+            vec.push(value.clone());
+
+            assert_eq!(Rc::strong_count(&value), 8);
+
+            let popped = vec.pop().unwrap();
+
+            assert_eq!(Rc::strong_count(&value), 8);
+            drop(popped);
+            assert_eq!(Rc::strong_count(&value), 7);
+        }
+
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_vec_zero_sized() {
+        let arena = Arena::new(16);
+
+        let mut vec = ArenaVec::new(&arena);
+
+        vec.extend((0..100).map(|_| ()));
+
+        assert_eq!(vec.len(), 100);
+        assert_eq!(arena.occupied(), 0);
+    }
+
+    #[test]
+    fn test_vec_eq() {
+        let arena = Arena::new(1024);
+
+        let mut vec = arena.make_vec(0).unwrap();
+        vec.extend([1, 2, 3]);
+
+        assert_eq!(vec, vec.clone());
+        assert_eq!(format!("{:?}", vec), "[1, 2, 3]");
+    }
+}
@@ -0,0 +1,163 @@
+use realms::{with_arena, with_sync_arena, Arena, ScopedAlloc, SyncArena};
+use std::thread;
+
+#[global_allocator]
+static GLOBAL: ScopedAlloc = ScopedAlloc;
+
+#[test]
+fn test_with_arena() {
+    let arena = Arena::new(4096);
+
+    let (sum, len, occupied) = unsafe {
+        with_arena(&arena, || {
+            let mut vec = Vec::with_capacity(100);
+            vec.extend(0..100u32);
+            let string = String::from("Hello, world!");
+
+            (vec.iter().sum::<u32>(), string.len(), arena.occupied())
+        })
+    };
+
+    assert_eq!(sum, 4950);
+    assert_eq!(len, 13);
+
+    // Both values live in the arena, std may round their capacity up.
+    assert!(occupied >= 100 * 4 + 13);
+
+    // Freeing in reverse order of allocation gave everything back.
+    assert_eq!(arena.occupied(), 0);
+}
+
+#[test]
+fn test_with_arena_grows_in_place() {
+    let arena = Arena::new(4096);
+
+    let (capacity, occupied) = unsafe {
+        with_arena(&arena, || {
+            let mut vec = Vec::new();
+
+            for i in 0..256u32 {
+                vec.push(i);
+            }
+
+            (vec.capacity(), arena.occupied())
+        })
+    };
+
+    // The vector was the only allocation, so every push grew it in place.
+    assert_eq!(occupied, capacity * 4);
+}
+
+#[test]
+fn test_outside_scope() {
+    let arena = Arena::new(4096);
+
+    unsafe { with_arena(&arena, || {}) };
+
+    let vec: Vec<u32> = (0..100).collect();
+
+    assert_eq!(vec.len(), 100);
+    assert_eq!(arena.occupied(), 0);
+}
+
+#[test]
+fn test_with_arena_nested() {
+    let outer = Arena::new(4096);
+    let inner = Arena::new(4096);
+
+    let (first, outer_occupied, inner_occupied) = unsafe {
+        with_arena(&outer, || {
+            let mut first = [1u64; 4].to_vec();
+
+            let inner_occupied = with_arena(&inner, || {
+                let second = [2u64; 8].to_vec();
+
+                // Growing the outer vector still extends it in its own arena.
+                first.push(second.iter().sum());
+                inner.occupied()
+            });
+
+            (first.iter().sum::<u64>(), outer.occupied(), inner_occupied)
+        })
+    };
+
+    assert_eq!(first, 20);
+    assert_eq!(outer_occupied, 64);
+    assert_eq!(inner_occupied, 64);
+    assert_eq!(outer.occupied(), 0);
+    assert_eq!(inner.occupied(), 0);
+}
+
+#[test]
+fn test_with_arena_fallback() {
+    let arena = Arena::new(64);
+
+    let sum = unsafe {
+        with_arena(&arena, || {
+            let vec: Vec<u64> = (0..1000).collect();
+            vec.iter().sum::<u64>()
+        })
+    };
+
+    assert_eq!(sum, 499500);
+    assert_eq!(arena.occupied(), 0);
+}
+
+#[test]
+fn test_with_arena_bookkeeping() {
+    let mut arena = Arena::growable(16, 4096);
+
+    unsafe {
+        with_arena(&arena, || {
+            arena.checkpoint();
+
+            // The drop list and the new chunk outlive the scope, so they
+            // must not be allocated in the arena itself.
+            for _ in 0..8 {
+                arena.push(String::new()).unwrap();
+            }
+        })
+    };
+
+    assert!(arena.chunk_count() > 1);
+
+    arena.reset();
+    drop(arena);
+}
+
+#[test]
+fn test_with_sync_arena() {
+    let arena = SyncArena::new(64 * 1024);
+
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            let arena = &arena;
+
+            scope.spawn(move || {
+                unsafe {
+                    with_sync_arena(arena, || {
+                        let vec: Vec<u64> = (0..100).collect();
+                        vec.len()
+                    })
+                }
+            });
+        }
+    });
+
+    assert!(arena.occupied() >= 4 * 800);
+}
+
+#[test]
+fn test_with_sync_arena_bookkeeping() {
+    let arena = SyncArena::new(1024);
+
+    unsafe {
+        with_sync_arena(&arena, || {
+            for _ in 0..8 {
+                arena.push(String::new()).unwrap();
+            }
+        })
+    };
+
+    drop(arena);
+}